@@ -1,3 +1,7 @@
 fn main() {
-    embuild::espidf::sysenv::output();
+    // The ESP-IDF environment only exists when building the firmware, host builds for the
+    // tests have nothing to pass on.
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("espidf") {
+        embuild::espidf::sysenv::output();
+    }
 }
@@ -0,0 +1,60 @@
+use super::Display;
+
+/// A display that keeps the last frame sent to it in memory, to run and test the graphics
+/// code without a panel.
+pub struct MemoryDisplay {
+    pub frame: Vec<Vec<u8>>,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl MemoryDisplay {
+    pub fn new(width: u16, height: u16) -> Self {
+        MemoryDisplay {
+            frame: vec![vec![0xFF; ((width + 7) / 8) as usize]; height as usize],
+            width,
+            height,
+        }
+    }
+
+    /// Color of a pixel as shown, `None` outside the display.
+    pub fn pixel(&self, x: u16, y: u16) -> Option<bool> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        Some(self.frame[y as usize][(x / 8) as usize] & (1 << (x % 8)) != 0)
+    }
+}
+
+impl Display for MemoryDisplay {
+    fn clear_display(&mut self) -> anyhow::Result<()> {
+        for line in self.frame.iter_mut() {
+            line.fill(0xFF);
+        }
+
+        Ok(())
+    }
+
+    fn refresh(&mut self, buffer: &Vec<Vec<u8>>) -> anyhow::Result<()> {
+        for (line, source) in self.frame.iter_mut().zip(buffer.iter()) {
+            let len = line.len().min(source.len());
+
+            line[..len].copy_from_slice(&source[..len]);
+        }
+
+        Ok(())
+    }
+
+    fn refresh_line(&mut self, line_num: u8, buffer: &[u8]) -> anyhow::Result<()> {
+        let line = self
+            .frame
+            .get_mut(line_num as usize)
+            .ok_or_else(|| anyhow::anyhow!("Line {} is out of bounds", line_num))?;
+        let len = line.len().min(buffer.len());
+
+        line[..len].copy_from_slice(&buffer[..len]);
+
+        Ok(())
+    }
+}
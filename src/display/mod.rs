@@ -1,5 +1,9 @@
 pub mod display;
+pub mod memory_display;
+#[cfg(target_os = "espidf")]
 pub mod sharp_memory;
 
 pub use display::*;
+pub use memory_display::*;
+#[cfg(target_os = "espidf")]
 pub use sharp_memory::*;
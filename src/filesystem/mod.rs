@@ -1,3 +1,16 @@
+pub mod read_font;
+pub mod read_image;
+pub mod read_sprite_sheet;
 pub mod read_texture;
+pub mod rle;
+pub mod screenshot;
+pub mod texture;
+pub mod texture_cache;
 
+pub use read_font::*;
+pub use read_image::*;
+pub use read_sprite_sheet::*;
 pub use read_texture::*;
+pub use screenshot::*;
+pub use texture::*;
+pub use texture_cache::*;
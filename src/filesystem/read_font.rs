@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+
+use anyhow::anyhow;
+
+use crate::graphics::{BitmapFont, BitmapGlyph, GlyphMetrics, KerningPair};
+
+pub const FONT_MAGIC: [u8; 4] = *b"EFNT";
+pub const FONT_VERSION: u8 = 1;
+pub const FONT_HEADER_LEN: usize = 18;
+
+const GLYPH_RECORD_LEN: usize = 18;
+const KERNING_RECORD_LEN: usize = 10;
+
+/// Serializes a [`BitmapFont`]. All multi-byte fields are little-endian.
+///
+/// | offset | size | field                          |
+/// |--------|------|--------------------------------|
+/// | 0      | 4    | magic, `b"EFNT"`               |
+/// | 4      | 1    | format version                 |
+/// | 5      | 1    | flags, must be 0               |
+/// | 6      | 2    | ascent                         |
+/// | 8      | 2    | descent                        |
+/// | 10     | 2    | glyph count                    |
+/// | 12     | 2    | kerning pair count             |
+/// | 14     | 4    | length of the bitmap data      |
+///
+/// The header is followed by glyph records (`u32` code point, width, height, x offset,
+/// y offset, advance and `u32` bitmap offset), kerning records (two `u32` code points and
+/// an `i16` adjustment) and finally the glyph bitmaps. Fails if there are more glyphs or
+/// kerning pairs, or more bitmap data, than the header can count.
+pub fn font_to_bytes(font: &BitmapFont) -> anyhow::Result<Vec<u8>> {
+    let glyph_count = u16::try_from(font.glyphs.len())
+        .map_err(|_| anyhow!("Font has {} glyphs, at most 65535 fit", font.glyphs.len()))?;
+    let kerning_count = u16::try_from(font.kerning.len()).map_err(|_| {
+        anyhow!(
+            "Font has {} kerning pairs, at most 65535 fit",
+            font.kerning.len()
+        )
+    })?;
+    let bitmap_len = u32::try_from(font.bitmaps.len())
+        .map_err(|_| anyhow!("Font has {} bytes of bitmaps", font.bitmaps.len()))?;
+    let mut bytes = FONT_MAGIC.to_vec();
+
+    bytes.push(FONT_VERSION);
+    bytes.push(0);
+    bytes.extend_from_slice(&font.ascent.to_le_bytes());
+    bytes.extend_from_slice(&font.descent.to_le_bytes());
+    bytes.extend_from_slice(&glyph_count.to_le_bytes());
+    bytes.extend_from_slice(&kerning_count.to_le_bytes());
+    bytes.extend_from_slice(&bitmap_len.to_le_bytes());
+
+    for glyph in font.glyphs.iter() {
+        bytes.extend_from_slice(&(glyph.chr as u32).to_le_bytes());
+        bytes.extend_from_slice(&glyph.metrics.width.to_le_bytes());
+        bytes.extend_from_slice(&glyph.metrics.height.to_le_bytes());
+        bytes.extend_from_slice(&glyph.metrics.x_offset.to_le_bytes());
+        bytes.extend_from_slice(&glyph.metrics.y_offset.to_le_bytes());
+        bytes.extend_from_slice(&glyph.metrics.advance.to_le_bytes());
+        bytes.extend_from_slice(&glyph.offset.to_le_bytes());
+    }
+
+    for pair in font.kerning.iter() {
+        bytes.extend_from_slice(&(pair.left as u32).to_le_bytes());
+        bytes.extend_from_slice(&(pair.right as u32).to_le_bytes());
+        bytes.extend_from_slice(&pair.adjust.to_le_bytes());
+    }
+
+    bytes.extend_from_slice(&font.bitmaps);
+    Ok(bytes)
+}
+
+pub fn parse_font(bytes: &[u8]) -> anyhow::Result<BitmapFont<'static>> {
+    if bytes.len() < FONT_HEADER_LEN || bytes[0..4] != FONT_MAGIC {
+        return Err(anyhow!("Not a font file, magic number does not match"));
+    }
+
+    if bytes[4] != FONT_VERSION {
+        return Err(anyhow!(
+            "Unsupported font version {}, expected {}",
+            bytes[4],
+            FONT_VERSION
+        ));
+    }
+
+    if bytes[5] != 0 {
+        return Err(anyhow!("Font uses unknown flags {:#04x}", bytes[5]));
+    }
+
+    let u16_at = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+    let u32_at = |offset: usize| {
+        u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ])
+    };
+    let char_at = |offset: usize| {
+        char::from_u32(u32_at(offset)).ok_or_else(|| anyhow!("Font contains an invalid character"))
+    };
+
+    let glyph_count = u16_at(10) as usize;
+    let kerning_count = u16_at(12) as usize;
+    let bitmap_len = u32_at(14) as usize;
+    let kerning_start = FONT_HEADER_LEN + glyph_count * GLYPH_RECORD_LEN;
+    let bitmap_start = kerning_start + kerning_count * KERNING_RECORD_LEN;
+    let declared_len = bitmap_start
+        .checked_add(bitmap_len)
+        .ok_or_else(|| anyhow!("Font header declares a bitmap of {} bytes", bitmap_len))?;
+
+    if bytes.len() != declared_len {
+        return Err(anyhow!(
+            "Font file is {} bytes long, header declares {}",
+            bytes.len(),
+            declared_len
+        ));
+    }
+
+    let mut glyphs = Vec::with_capacity(glyph_count);
+
+    for i in 0..glyph_count {
+        let record = FONT_HEADER_LEN + i * GLYPH_RECORD_LEN;
+        let glyph = BitmapGlyph {
+            chr: char_at(record)?,
+            metrics: GlyphMetrics {
+                width: u16_at(record + 4),
+                height: u16_at(record + 6),
+                x_offset: u16_at(record + 8) as i16,
+                y_offset: u16_at(record + 10) as i16,
+                advance: u16_at(record + 12),
+            },
+            offset: u32_at(record + 14),
+        };
+        let end = (glyph.offset as usize)
+            .checked_add(glyph.metrics.bitmap_len())
+            .filter(|end| *end <= bitmap_len);
+
+        if end.is_none() {
+            return Err(anyhow!("Bitmap of glyph {:?} is out of bounds", glyph.chr));
+        }
+
+        if glyphs
+            .last()
+            .is_some_and(|last: &BitmapGlyph| last.chr >= glyph.chr)
+        {
+            return Err(anyhow!("Font glyphs are not sorted"));
+        }
+
+        glyphs.push(glyph);
+    }
+
+    let mut kerning = Vec::with_capacity(kerning_count);
+
+    for i in 0..kerning_count {
+        let record = kerning_start + i * KERNING_RECORD_LEN;
+        let pair = KerningPair {
+            left: char_at(record)?,
+            right: char_at(record + 4)?,
+            adjust: u16_at(record + 8) as i16,
+        };
+
+        if kerning
+            .last()
+            .is_some_and(|last: &KerningPair| (last.left, last.right) >= (pair.left, pair.right))
+        {
+            return Err(anyhow!("Font kerning pairs are not sorted"));
+        }
+
+        kerning.push(pair);
+    }
+
+    Ok(BitmapFont {
+        ascent: u16_at(6),
+        descent: u16_at(8),
+        glyphs: Cow::Owned(glyphs),
+        bitmaps: Cow::Owned(bytes[bitmap_start..].to_vec()),
+        kerning: Cow::Owned(kerning),
+    })
+}
+
+pub fn load_font(file_path: &str) -> anyhow::Result<BitmapFont<'static>> {
+    let bytes = std::fs::read(file_path)?;
+    parse_font(&bytes).map_err(|err| anyhow!("Invalid font {}: {}", file_path, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font(kerning: Vec<KerningPair>) -> BitmapFont<'static> {
+        BitmapFont {
+            ascent: 7,
+            descent: 1,
+            glyphs: Cow::Owned(vec![BitmapGlyph {
+                chr: 'A',
+                metrics: GlyphMetrics {
+                    width: 3,
+                    height: 2,
+                    x_offset: 0,
+                    y_offset: 7,
+                    advance: 4,
+                },
+                offset: 0,
+            }]),
+            bitmaps: Cow::Owned(vec![0b101, 0b010]),
+            kerning: Cow::Owned(kerning),
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let pair = KerningPair {
+            left: 'A',
+            right: 'V',
+            adjust: -1,
+        };
+        let font = font(vec![pair]);
+        let parsed = parse_font(&font_to_bytes(&font).unwrap()).unwrap();
+
+        assert_eq!(parsed.glyphs, font.glyphs);
+        assert_eq!(parsed.bitmaps, font.bitmaps);
+        assert_eq!(parsed.kerning, font.kerning);
+    }
+
+    #[test]
+    fn rejects_more_kerning_pairs_than_the_header_counts() {
+        let pairs = (0..=u16::MAX as u32)
+            .map(|i| KerningPair {
+                left: 'A',
+                right: char::from_u32(0x10000 + i).unwrap(),
+                adjust: 1,
+            })
+            .collect();
+
+        assert!(font_to_bytes(&font(pairs)).is_err());
+    }
+}
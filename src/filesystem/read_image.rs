@@ -0,0 +1,343 @@
+use anyhow::anyhow;
+
+use super::{Texture, TEXTURE_MAGIC};
+
+/// Loads a texture, PBM (P1/P4), XBM or 1-bit BMP file, picking the decoder from the
+/// file contents rather than its extension.
+pub fn load_image(file_path: &str) -> anyhow::Result<Texture> {
+    let bytes = std::fs::read(file_path)?;
+
+    let image = if bytes.starts_with(&TEXTURE_MAGIC) {
+        Texture::parse(&bytes)
+    } else if bytes.starts_with(b"P1") || bytes.starts_with(b"P4") {
+        decode_pbm(&bytes)
+    } else if bytes.starts_with(b"BM") {
+        decode_bmp(&bytes)
+    } else if bytes.starts_with(b"#define") {
+        decode_xbm(&bytes)
+    } else {
+        Err(anyhow!("Unrecognised image format"))
+    };
+
+    image.map_err(|err| anyhow!("Invalid image {}: {}", file_path, err))
+}
+
+pub fn decode_pbm(bytes: &[u8]) -> anyhow::Result<Texture> {
+    let mut pos = 2;
+    let width = pbm_header_value(bytes, &mut pos)?;
+    let height = pbm_header_value(bytes, &mut pos)?;
+    let stride = (width as usize + 7) / 8;
+    let truncated = || anyhow!("PBM raster is shorter than {}x{}", width, height);
+
+    if bytes.starts_with(b"P4") {
+        // A single whitespace byte separates the header from the raster.
+        let raster = stride
+            .checked_mul(height as usize)
+            .and_then(|len| bytes.get(pos + 1..(pos + 1).checked_add(len)?))
+            .ok_or_else(truncated)?;
+
+        return Texture::new(
+            width,
+            height,
+            raster.iter().map(|b| !b.reverse_bits()).collect(),
+        );
+    }
+
+    // Every pixel takes at least one character of the plain raster.
+    if ((bytes.len() - pos) as u64) < width as u64 * height as u64 {
+        return Err(truncated());
+    }
+
+    let mut data = vec![0xFF; stride * height as usize];
+    let mut bits = bytes[pos..].iter().filter(|b| **b == b'0' || **b == b'1');
+
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            match bits.next() {
+                Some(b'1') => data[y * stride + x / 8] &= !(1 << (x % 8)),
+                Some(_) => {}
+                None => return Err(truncated()),
+            }
+        }
+    }
+
+    Texture::new(width, height, data)
+}
+
+fn pbm_header_value(bytes: &[u8], pos: &mut usize) -> anyhow::Result<u16> {
+    loop {
+        match bytes.get(*pos) {
+            Some(b'#') => {
+                while !matches!(bytes.get(*pos), Some(b'\n') | None) {
+                    *pos += 1;
+                }
+            }
+            Some(b) if b.is_ascii_whitespace() => *pos += 1,
+            Some(_) => break,
+            None => return Err(anyhow!("PBM header is truncated")),
+        }
+    }
+
+    let start = *pos;
+
+    while bytes.get(*pos).is_some_and(u8::is_ascii_digit) {
+        *pos += 1;
+    }
+
+    std::str::from_utf8(&bytes[start..*pos])?
+        .parse::<u16>()
+        .map_err(|_| anyhow!("PBM header has an invalid dimension"))
+}
+
+pub fn decode_xbm(bytes: &[u8]) -> anyhow::Result<Texture> {
+    let text = std::str::from_utf8(bytes)?;
+    let mut width = None;
+    let mut height = None;
+
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+
+        if words.next() != Some("#define") {
+            continue;
+        }
+
+        match (words.next(), words.next()) {
+            (Some(name), Some(value)) if name.ends_with("_width") => width = Some(value.parse()?),
+            (Some(name), Some(value)) if name.ends_with("_height") => height = Some(value.parse()?),
+            _ => {}
+        }
+    }
+
+    let (width, height): (u16, u16) = match (width, height) {
+        (Some(w), Some(h)) => (w, h),
+        _ => return Err(anyhow!("XBM file does not define its width and height")),
+    };
+
+    let body = text
+        .find('{')
+        .map(|start| &text[start + 1..])
+        .ok_or_else(|| anyhow!("XBM file has no pixel data"))?;
+
+    // XBM shares the framebuffer bit order but a set bit is black.
+    let data = body
+        .split([',', '}'])
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && !value.starts_with(';'))
+        .map(|value| {
+            u8::from_str_radix(value.trim_start_matches("0x").trim_start_matches("0X"), 16)
+                .map(|byte| !byte)
+                .map_err(|_| anyhow!("XBM pixel value {} is not a hex byte", value))
+        })
+        .collect::<anyhow::Result<Vec<u8>>>()?;
+
+    Texture::new(width, height, data)
+}
+
+pub fn decode_bmp(bytes: &[u8]) -> anyhow::Result<Texture> {
+    let u16_at = |offset: usize| -> anyhow::Result<u16> {
+        bytes
+            .get(offset..offset + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| anyhow!("BMP header is truncated"))
+    };
+    let u32_at = |offset: usize| -> anyhow::Result<u32> {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| anyhow!("BMP header is truncated"))
+    };
+
+    let data_offset = u32_at(10)? as usize;
+    let dib_size = u32_at(14)? as usize;
+    let raw_width = u32_at(18)? as i32;
+    let raw_height = u32_at(22)? as i32;
+
+    if u16_at(28)? != 1 {
+        return Err(anyhow!("Only 1-bit BMP files are supported"));
+    }
+
+    if u32_at(30)? != 0 {
+        return Err(anyhow!("Compressed BMP files are not supported"));
+    }
+
+    let width: u16 = raw_width.try_into()?;
+    let height: u16 = raw_height.unsigned_abs().try_into()?;
+    let top_down = raw_height < 0;
+
+    // Palette entries are stored as BGRA, index 0 first.
+    let palette = 14usize
+        .checked_add(dib_size)
+        .and_then(|start| bytes.get(start..start.checked_add(8)?))
+        .ok_or_else(|| anyhow!("BMP palette is truncated"))?;
+    let is_white = |entry: &[u8]| entry[0] as u16 + entry[1] as u16 + entry[2] as u16 >= 384;
+    let (zero_white, one_white) = (is_white(&palette[0..4]), is_white(&palette[4..8]));
+
+    let stride = (width as usize + 7) / 8;
+    let bmp_stride = (stride + 3) & !3;
+
+    // Rows are padded to 4 bytes, except that the last one may end with its pixels.
+    let data_end = (height as usize)
+        .checked_sub(1)
+        .map_or(Some(0), |rows| {
+            rows.checked_mul(bmp_stride)?.checked_add(stride)
+        })
+        .and_then(|len| data_offset.checked_add(len));
+
+    if !data_end.is_some_and(|end| end <= bytes.len()) {
+        return Err(anyhow!("BMP pixel data is truncated"));
+    }
+
+    let mut data = vec![0u8; stride * height as usize];
+
+    for y in 0..height as usize {
+        let src_row = if top_down { y } else { height as usize - 1 - y };
+        let start = data_offset + src_row * bmp_stride;
+        let src = &bytes[start..start + stride];
+
+        for (dest, byte) in data[y * stride..(y + 1) * stride].iter_mut().zip(src) {
+            let bits = byte.reverse_bits();
+            *dest = match (zero_white, one_white) {
+                (false, true) => bits,
+                (true, false) => !bits,
+                (true, true) => 0xFF,
+                (false, false) => 0x00,
+            };
+        }
+    }
+
+    Texture::new(width, height, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every decoder is given the same 3x2 image, black-white-black over white-black-white.
+    const EXPECTED: [u8; 2] = [0b010, 0b101];
+
+    fn pixels(texture: &Texture) -> Vec<u8> {
+        texture.data.iter().map(|byte| byte & 0b111).collect()
+    }
+
+    /// A 1-bit BMP with a black and white palette and rows given top to bottom, `top_down`
+    /// storing them in that order with a negative height.
+    fn bmp(width: i32, rows: &[u8], top_down: bool) -> Vec<u8> {
+        let height = if top_down {
+            -(rows.len() as i32)
+        } else {
+            rows.len() as i32
+        };
+        let mut bytes = b"BM".to_vec();
+
+        bytes.extend_from_slice(&(62 + rows.len() as u32 * 4).to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&62u32.to_le_bytes());
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&[0; 24]);
+        bytes.extend_from_slice(&[0, 0, 0, 0, 255, 255, 255, 0]);
+
+        let mut stored = rows.to_vec();
+
+        if !top_down {
+            stored.reverse();
+        }
+
+        for row in stored {
+            bytes.extend_from_slice(&[row, 0, 0, 0]);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn decodes_plain_and_raw_pbm() {
+        let plain = decode_pbm(b"P1\n# comment\n3 2\n1 0 1\n0 1 0\n").unwrap();
+        let raw = decode_pbm(b"P4 3 2\n\xA0\x40").unwrap();
+
+        for texture in [plain, raw] {
+            assert_eq!((texture.width(), texture.height()), (3, 2));
+            assert_eq!(pixels(&texture), EXPECTED);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_pbm() {
+        assert!(decode_pbm(b"P4\n3").is_err());
+        assert!(decode_pbm(b"P4\n70000 1\n").is_err());
+        assert!(decode_pbm(b"P4\n3 2\n\xA0").is_err());
+        assert!(decode_pbm(b"P1\n3 2\n1 0 1\n0 1").is_err());
+        assert!(decode_pbm(b"P4\n65535 1\n").is_err());
+        assert!(decode_pbm(b"P4\n65535 65535\n").is_err());
+        assert!(decode_pbm(b"P1\n65535 65535\n").is_err());
+    }
+
+    #[test]
+    fn decodes_xbm() {
+        let xbm = b"#define icon_width 3
+#define icon_height 2
+static char icon_bits[] = {
+  0x05, 0x02 };
+";
+        let texture = decode_xbm(xbm).unwrap();
+
+        assert_eq!((texture.width(), texture.height()), (3, 2));
+        assert_eq!(pixels(&texture), EXPECTED);
+    }
+
+    #[test]
+    fn rejects_malformed_xbm() {
+        assert!(
+            decode_xbm(b"#define icon_width 3\nstatic char icon_bits[] = { 0x05, 0x02 };").is_err()
+        );
+        assert!(decode_xbm(b"#define icon_width 3\n#define icon_height 2\n").is_err());
+        assert!(
+            decode_xbm(b"#define icon_width 3\n#define icon_height 2\n{ 0x05, 0xZZ };").is_err()
+        );
+        assert!(decode_xbm(b"#define icon_width 3\n#define icon_height 2\n{ 0x05 };").is_err());
+    }
+
+    #[test]
+    fn decodes_bottom_up_and_top_down_bmp() {
+        for top_down in [false, true] {
+            let texture = decode_bmp(&bmp(3, &[0b0100_0000, 0b1010_0000], top_down)).unwrap();
+
+            assert_eq!((texture.width(), texture.height()), (3, 2));
+            assert_eq!(pixels(&texture), EXPECTED);
+        }
+    }
+
+    #[test]
+    fn follows_the_bmp_palette() {
+        let mut bytes = bmp(3, &[0b1010_0000, 0b0100_0000], false);
+
+        // Index 0 white and index 1 black.
+        bytes[54..62].copy_from_slice(&[255, 255, 255, 0, 0, 0, 0, 0]);
+
+        assert_eq!(pixels(&decode_bmp(&bytes).unwrap()), EXPECTED);
+    }
+
+    #[test]
+    fn rejects_malformed_bmp() {
+        let bytes = bmp(3, &[0b0100_0000, 0b1010_0000], false);
+        let with = |offset: usize, value: &[u8]| {
+            let mut bytes = bytes.clone();
+            bytes[offset..offset + value.len()].copy_from_slice(value);
+            decode_bmp(&bytes)
+        };
+
+        assert!(decode_bmp(&bytes[..20]).is_err());
+        // The last row only needs its pixel bytes, not the padding.
+        assert!(decode_bmp(&bytes[..bytes.len() - 3]).is_ok());
+        assert!(decode_bmp(&bytes[..bytes.len() - 4]).is_err());
+        assert!(with(28, &8u16.to_le_bytes()).is_err());
+        assert!(with(30, &1u32.to_le_bytes()).is_err());
+        assert!(with(10, &u32::MAX.to_le_bytes()).is_err());
+        assert!(with(14, &u32::MAX.to_le_bytes()).is_err());
+        assert!(with(18, &(-3i32).to_le_bytes()).is_err());
+        assert!(with(22, &65535i32.to_le_bytes()).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::anyhow;
+
+const SPRITE_HEADER_LEN: usize = 8;
+
+/// Frames of an animation stored one after another in a single file.
+///
+/// The file starts with four little-endian `u16` values: frame width, frame height,
+/// frame count and frame duration in milliseconds. Frames follow, each stored as
+/// `frame_height` rows of `ceil(frame_width / 8)` bytes in the same bit order as the
+/// framebuffer (least significant bit is the leftmost pixel, set bit is white).
+pub struct SpriteSheet {
+    pub frame_width: u16,
+    pub frame_height: u16,
+    pub frame_count: u16,
+    pub frame_duration_ms: u16,
+    pub frames: Vec<u8>,
+}
+
+impl SpriteSheet {
+    pub fn stride(&self) -> usize {
+        (self.frame_width as usize + 7) / 8
+    }
+
+    pub fn frame_len(&self) -> usize {
+        self.stride() * self.frame_height as usize
+    }
+
+    pub fn frame(&self, index: u16) -> Option<&[u8]> {
+        if index >= self.frame_count {
+            return None;
+        }
+
+        let start = (index as usize).checked_mul(self.frame_len())?;
+        self.frames.get(start..start.checked_add(self.frame_len())?)
+    }
+}
+
+pub fn read_sprite_sheet(file_path: &str) -> anyhow::Result<SpriteSheet> {
+    let mut data = std::fs::read(file_path)?;
+
+    if data.len() < SPRITE_HEADER_LEN {
+        return Err(anyhow!("Sprite sheet {} is missing its header", file_path));
+    }
+
+    let frames = data.split_off(SPRITE_HEADER_LEN);
+    let sheet = SpriteSheet {
+        frame_width: u16::from_le_bytes([data[0], data[1]]),
+        frame_height: u16::from_le_bytes([data[2], data[3]]),
+        frame_count: u16::from_le_bytes([data[4], data[5]]),
+        frame_duration_ms: u16::from_le_bytes([data[6], data[7]]),
+        frames,
+    };
+
+    if sheet.frame_count == 0 || sheet.frame_width == 0 || sheet.frame_height == 0 {
+        return Err(anyhow!("Sprite sheet {} has no frames", file_path));
+    }
+
+    let frames_len = sheet
+        .frame_len()
+        .checked_mul(sheet.frame_count as usize)
+        .ok_or_else(|| anyhow!("Sprite sheet {} has too much frame data", file_path))?;
+
+    if sheet.frames.len() != frames_len {
+        return Err(anyhow!(
+            "Sprite sheet {} should hold {} bytes of frame data, found {}",
+            file_path,
+            frames_len,
+            sheet.frames.len()
+        ));
+    }
+
+    Ok(sheet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(name: &str, header: [u16; 4], frames: &[u8]) -> anyhow::Result<SpriteSheet> {
+        let path = std::env::temp_dir().join(name);
+        let mut bytes: Vec<u8> = header
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+
+        bytes.extend_from_slice(frames);
+        std::fs::write(&path, bytes)?;
+
+        let sheet = read_sprite_sheet(path.to_str().unwrap());
+        std::fs::remove_file(&path)?;
+        sheet
+    }
+
+    #[test]
+    fn reads_frames() {
+        let sheet = read("sprite_frames.bin", [9, 2, 3, 40], &[0; 12]).unwrap();
+
+        assert_eq!(sheet.stride(), 2);
+        assert_eq!(sheet.frame(2), Some(&[0u8; 4][..]));
+        assert_eq!(sheet.frame(3), None);
+    }
+
+    #[test]
+    fn rejects_sheets_of_the_wrong_length() {
+        assert!(read("sprite_short.bin", [9, 2, 3, 40], &[0; 11]).is_err());
+        assert!(read("sprite_empty.bin", [9, 2, 0, 40], &[]).is_err());
+    }
+
+    #[test]
+    fn rejects_huge_frames_without_overflowing() {
+        assert!(read("sprite_wide.bin", [u16::MAX, 1, 1, 40], &[0; 16]).is_err());
+        assert!(read("sprite_huge.bin", [u16::MAX, u16::MAX, u16::MAX, 40], &[]).is_err());
+    }
+}
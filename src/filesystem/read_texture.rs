@@ -1,5 +1,13 @@
+#[cfg(target_os = "espidf")]
 use std::ffi::CString;
+use std::fs::File;
+use std::io::BufReader;
 
+use anyhow::anyhow;
+
+use super::TextureReader;
+
+#[cfg(target_os = "espidf")]
 pub fn register_spiffs_partition(mount_point: &str, partition_name: &str) -> anyhow::Result<()> {
     let base_path = CString::new(mount_point)?;
     let partition = CString::new(partition_name)?;
@@ -19,11 +27,21 @@ pub fn register_spiffs_partition(mount_point: &str, partition_name: &str) -> any
 }
 
 pub fn read_texture_to_buffer(file_path: &str) -> anyhow::Result<Vec<Vec<u8>>> {
-    let mut texture = std::fs::read(file_path).map_err(anyhow::Error::from)?;
-    let w = ((texture[0] as u16) << 8) | texture[1] as u16;
-    let _dims = texture.split_off(4);
-    Ok(texture
-        .chunks(w as usize)
-        .map(|el| el.to_vec())
-        .collect::<Vec<Vec<u8>>>())
+    read_texture_rows(file_path).map_err(|err| anyhow!("Invalid texture {}: {}", file_path, err))
+}
+
+fn read_texture_rows(file_path: &str) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut reader = TextureReader::new(BufReader::new(File::open(file_path)?))?;
+    let header = *reader.header();
+    let mut rows = Vec::with_capacity(header.height as usize);
+
+    for _ in 0..header.height {
+        let mut row = vec![0; header.stride as usize];
+        reader.read_row(&mut row)?;
+        rows.push(row);
+    }
+
+    reader.finish()?;
+
+    Ok(rows)
 }
@@ -0,0 +1,134 @@
+use std::io::{Error, ErrorKind, Read};
+
+const MAX_RUN: usize = 128;
+
+/// Appends `row` to `out` using PackBits: a control byte `n` of 0..=127 is followed by
+/// `n + 1` literal bytes, 129..=255 means the next byte repeats `257 - n` times.
+pub fn encode_row(row: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+
+    while i < row.len() {
+        let mut run = 1;
+
+        while i + run < row.len() && run < MAX_RUN && row[i + run] == row[i] {
+            run += 1;
+        }
+
+        if run >= 2 {
+            out.push((257 - run) as u8);
+            out.push(row[i]);
+            i += run;
+            continue;
+        }
+
+        let start = i;
+
+        while i < row.len() && i - start < MAX_RUN {
+            if i + 2 < row.len() && row[i] == row[i + 1] && row[i] == row[i + 2] {
+                break;
+            }
+
+            i += 1;
+        }
+
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&row[start..i]);
+    }
+}
+
+/// Decodes exactly one row previously written by [`encode_row`].
+pub fn decode_row<R: Read>(reader: &mut R, row: &mut [u8]) -> std::io::Result<()> {
+    let mut filled = 0;
+
+    while filled < row.len() {
+        let mut control = [0u8; 1];
+        reader.read_exact(&mut control)?;
+
+        let (len, repeat) = match control[0] {
+            n @ 0..=127 => (n as usize + 1, false),
+            128 => continue,
+            n => (257 - n as usize, true),
+        };
+
+        if filled + len > row.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "RLE run crosses a row boundary",
+            ));
+        }
+
+        if repeat {
+            let mut value = [0u8; 1];
+            reader.read_exact(&mut value)?;
+            row[filled..filled + len].fill(value[0]);
+        } else {
+            reader.read_exact(&mut row[filled..filled + len])?;
+        }
+
+        filled += len;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(row: &[u8]) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        encode_row(row, &mut encoded);
+
+        let mut decoded = vec![0; row.len()];
+        decode_row(&mut &encoded[..], &mut decoded).unwrap();
+
+        decoded
+    }
+
+    #[test]
+    fn round_trips_runs_and_literals() {
+        let mut row = vec![0xFF; 300];
+        row.extend((0..200).map(|i| i as u8));
+        row.extend([7, 7, 1, 7, 7, 7, 2]);
+
+        assert_eq!(round_trip(&row), row);
+        assert_eq!(round_trip(&[]), []);
+        assert_eq!(round_trip(&[0x42]), [0x42]);
+    }
+
+    #[test]
+    fn compresses_runs() {
+        let mut encoded = Vec::new();
+        encode_row(&[0; 128], &mut encoded);
+
+        assert_eq!(encoded, [129, 0]);
+    }
+
+    #[test]
+    fn rejects_truncated_rows() {
+        let mut encoded = Vec::new();
+        encode_row(&[1, 2, 3, 4], &mut encoded);
+
+        for len in 0..encoded.len() {
+            let mut row = [0; 4];
+
+            assert!(decode_row(&mut &encoded[..len], &mut row).is_err());
+        }
+    }
+
+    #[test]
+    fn rejects_runs_past_the_end_of_the_row() {
+        let mut row = [0; 4];
+
+        assert!(decode_row(&mut &[251u8, 0][..], &mut row).is_err());
+        assert!(decode_row(&mut &[4u8, 1, 2, 3, 4, 5][..], &mut row).is_err());
+    }
+
+    #[test]
+    fn skips_no_op_control_bytes() {
+        let mut row = [0; 2];
+        decode_row(&mut &[128u8, 1, 9, 8][..], &mut row).unwrap();
+
+        assert_eq!(row, [9, 8]);
+    }
+}
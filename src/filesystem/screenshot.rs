@@ -0,0 +1,106 @@
+use std::io::Write;
+
+use anyhow::anyhow;
+
+use super::crc32;
+
+/// Starts a framed screenshot on the serial console, followed by the data length in bytes.
+pub const SCREENSHOT_BEGIN: &str = "-----BEGIN SCREENSHOT";
+/// Ends a framed screenshot, followed by the CRC-32 of the data in hex.
+pub const SCREENSHOT_END: &str = "-----END SCREENSHOT";
+
+// Keeps lines short enough for the monitor not to wrap them.
+const FRAME_LINE_BYTES: usize = 48;
+
+/// Encodes rows of `ceil(width / 8)` bytes in framebuffer bit order, least significant bit
+/// leftmost and set bits white, as a binary PBM (`P4`) file, which stores the leftmost pixel
+/// in the most significant bit and black as set.
+pub fn encode_pbm(width: u16, height: u16, rows: &[u8]) -> Vec<u8> {
+    let stride = ((width + 7) / 8) as usize;
+    let mut pbm = format!("P4\n{} {}\n", width, height).into_bytes();
+
+    pbm.extend(
+        rows.iter()
+            .take(stride * height as usize)
+            .map(|byte| !byte.reverse_bits()),
+    );
+
+    pbm
+}
+
+/// Writes `data` as lines of hex between [`SCREENSHOT_BEGIN`] and [`SCREENSHOT_END`], so it
+/// survives a text console and can be picked out of a log with [`read_screenshot_frames`].
+pub fn write_screenshot_frame<W: Write>(writer: &mut W, data: &[u8]) -> anyhow::Result<()> {
+    writeln!(writer, "{} {}-----", SCREENSHOT_BEGIN, data.len())?;
+
+    for chunk in data.chunks(FRAME_LINE_BYTES) {
+        let line: String = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        writeln!(writer, "{}", line)?;
+    }
+
+    writeln!(writer, "{} {:08x}-----", SCREENSHOT_END, crc32(data))?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Finds every frame written by [`write_screenshot_frame`] in a captured console log,
+/// skipping the log lines around them. Fails if a frame is cut short or its CRC does not
+/// match, which happens when other output got mixed into it.
+pub fn read_screenshot_frames(log: &str) -> anyhow::Result<Vec<Vec<u8>>> {
+    let mut frames = Vec::new();
+    let mut lines = log.lines().map(str::trim);
+
+    while let Some(line) = lines.next() {
+        let len = match marker_value(line, SCREENSHOT_BEGIN) {
+            Some(len) => len
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Invalid screenshot length: {}", line))?,
+            None => continue,
+        };
+        let mut data = Vec::with_capacity(len);
+
+        let crc = loop {
+            let line = lines
+                .next()
+                .ok_or_else(|| anyhow!("Screenshot {} is cut short", frames.len() + 1))?;
+
+            if let Some(crc) = marker_value(line, SCREENSHOT_END) {
+                break u32::from_str_radix(crc, 16)
+                    .map_err(|_| anyhow!("Invalid screenshot CRC: {}", line))?;
+            }
+
+            if line.len() % 2 != 0 {
+                return Err(anyhow!("Invalid screenshot line: {}", line));
+            }
+
+            for i in (0..line.len()).step_by(2) {
+                let byte = line
+                    .get(i..i + 2)
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .ok_or_else(|| anyhow!("Invalid screenshot line: {}", line))?;
+
+                data.push(byte);
+            }
+        };
+
+        if data.len() != len || crc32(&data) != crc {
+            return Err(anyhow!(
+                "Screenshot {} is damaged, other output may have been printed into it",
+                frames.len() + 1
+            ));
+        }
+
+        frames.push(data);
+    }
+
+    Ok(frames)
+}
+
+/// The value between `marker` and the closing dashes, when `line` contains the marker.
+fn marker_value<'l>(line: &'l str, marker: &str) -> Option<&'l str> {
+    let start = line.find(marker)? + marker.len();
+
+    Some(line[start..].trim().trim_end_matches('-'))
+}
@@ -0,0 +1,477 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use anyhow::anyhow;
+
+use super::rle;
+
+pub const TEXTURE_MAGIC: [u8; 4] = *b"ETEX";
+pub const TEXTURE_VERSION: u8 = 1;
+pub const TEXTURE_HEADER_LEN: usize = 16;
+
+/// Pixel rows are PackBits compressed, see [`rle::encode_row`].
+pub const TEXTURE_FLAG_RLE: u8 = 0x01;
+
+/// Mask of all flag bits this version of the loader understands.
+pub const TEXTURE_KNOWN_FLAGS: u8 = TEXTURE_FLAG_RLE;
+
+/// Header of a texture file. All multi-byte fields are little-endian.
+///
+/// | offset | size | field                              |
+/// |--------|------|------------------------------------|
+/// | 0      | 4    | magic, `b"ETEX"`                   |
+/// | 4      | 1    | format version                     |
+/// | 5      | 1    | flags                              |
+/// | 6      | 2    | width in pixels                    |
+/// | 8      | 2    | height in pixels                   |
+/// | 10     | 2    | stride, bytes per row              |
+/// | 12     | 4    | CRC-32 (IEEE) of the pixel data    |
+///
+/// Pixel data follows the header as `height` rows of `stride` bytes in framebuffer bit
+/// order: the least significant bit is the leftmost pixel and a set bit is white. With
+/// [`TEXTURE_FLAG_RLE`] set every row is compressed on its own, so rows can be decoded one at
+/// a time. The CRC always covers the uncompressed rows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureHeader {
+    pub version: u8,
+    pub flags: u8,
+    pub width: u16,
+    pub height: u16,
+    pub stride: u16,
+    pub crc: u32,
+}
+
+impl TextureHeader {
+    pub fn new(width: u16, height: u16) -> Self {
+        TextureHeader {
+            version: TEXTURE_VERSION,
+            flags: 0,
+            width,
+            height,
+            stride: ((width as usize + 7) / 8) as u16,
+            crc: 0,
+        }
+    }
+
+    pub fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() < TEXTURE_HEADER_LEN {
+            return Err(anyhow!(
+                "Texture header is {} bytes long, expected {}",
+                bytes.len(),
+                TEXTURE_HEADER_LEN
+            ));
+        }
+
+        if bytes[0..4] != TEXTURE_MAGIC {
+            return Err(anyhow!("Not a texture file, magic number does not match"));
+        }
+
+        let header = TextureHeader {
+            version: bytes[4],
+            flags: bytes[5],
+            width: u16::from_le_bytes([bytes[6], bytes[7]]),
+            height: u16::from_le_bytes([bytes[8], bytes[9]]),
+            stride: u16::from_le_bytes([bytes[10], bytes[11]]),
+            crc: u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+        };
+
+        if header.version != TEXTURE_VERSION {
+            return Err(anyhow!(
+                "Unsupported texture version {}, expected {}",
+                header.version,
+                TEXTURE_VERSION
+            ));
+        }
+
+        if header.flags & !TEXTURE_KNOWN_FLAGS != 0 {
+            return Err(anyhow!("Texture uses unknown flags {:#04x}", header.flags));
+        }
+
+        if (header.stride as u32) * 8 < header.width as u32 {
+            return Err(anyhow!(
+                "Texture stride of {} bytes cannot hold {} pixels",
+                header.stride,
+                header.width
+            ));
+        }
+
+        Ok(header)
+    }
+
+    pub fn to_bytes(&self) -> [u8; TEXTURE_HEADER_LEN] {
+        let mut bytes = [0; TEXTURE_HEADER_LEN];
+
+        bytes[0..4].copy_from_slice(&TEXTURE_MAGIC);
+        bytes[4] = self.version;
+        bytes[5] = self.flags;
+        bytes[6..8].copy_from_slice(&self.width.to_le_bytes());
+        bytes[8..10].copy_from_slice(&self.height.to_le_bytes());
+        bytes[10..12].copy_from_slice(&self.stride.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.crc.to_le_bytes());
+
+        bytes
+    }
+
+    pub fn data_len(&self) -> usize {
+        self.stride as usize * self.height as usize
+    }
+}
+
+pub struct Texture {
+    pub header: TextureHeader,
+    pub data: Vec<u8>,
+}
+
+impl Texture {
+    pub fn new(width: u16, height: u16, data: Vec<u8>) -> anyhow::Result<Self> {
+        let mut header = TextureHeader::new(width, height);
+
+        if data.len() != header.data_len() {
+            return Err(anyhow!(
+                "Texture of {}x{} needs {} bytes of pixel data, got {}",
+                width,
+                height,
+                header.data_len(),
+                data.len()
+            ));
+        }
+
+        header.crc = crc32(&data);
+
+        Ok(Texture { header, data })
+    }
+
+    pub fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = TextureReader::new(bytes)?;
+        let mut header = *reader.header();
+        let stride = header.stride as usize;
+        let mut data = vec![0; header.data_len()];
+
+        for y in 0..header.height as usize {
+            reader.read_row(&mut data[y * stride..(y + 1) * stride])?;
+        }
+
+        reader.finish()?;
+
+        header.flags &= !TEXTURE_FLAG_RLE;
+
+        Ok(Texture { header, data })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header.to_bytes().to_vec();
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    pub fn to_rle_bytes(&self) -> Vec<u8> {
+        let header = TextureHeader {
+            flags: self.header.flags | TEXTURE_FLAG_RLE,
+            ..self.header
+        };
+        let mut bytes = header.to_bytes().to_vec();
+
+        for y in 0..self.height() {
+            rle::encode_row(self.row(y), &mut bytes);
+        }
+
+        bytes
+    }
+
+    pub fn width(&self) -> u16 {
+        self.header.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.header.height
+    }
+
+    pub fn stride(&self) -> usize {
+        self.header.stride as usize
+    }
+
+    pub fn row(&self, y: u16) -> &[u8] {
+        &self.data[y as usize * self.stride()..(y as usize + 1) * self.stride()]
+    }
+}
+
+/// Reads a texture one row at a time, so it never has to be held in memory as a whole.
+pub struct TextureReader<R: Read> {
+    header: TextureHeader,
+    reader: R,
+    rows_read: u16,
+    crc: u32,
+    seeked: bool,
+    scratch: Vec<u8>,
+    scratch_row: Option<u16>,
+}
+
+impl<R: Read> TextureReader<R> {
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let mut bytes = [0; TEXTURE_HEADER_LEN];
+
+        reader
+            .read_exact(&mut bytes)
+            .map_err(|_| anyhow!("Texture header is truncated"))?;
+
+        Ok(TextureReader {
+            header: TextureHeader::parse(&bytes)?,
+            reader,
+            rows_read: 0,
+            crc: !0,
+            seeked: false,
+            scratch: Vec::new(),
+            scratch_row: None,
+        })
+    }
+
+    pub fn header(&self) -> &TextureHeader {
+        &self.header
+    }
+
+    /// Decodes the next row into `row`, which must be exactly `stride` bytes long.
+    pub fn read_row(&mut self, row: &mut [u8]) -> anyhow::Result<()> {
+        if self.seeked {
+            return Err(anyhow!(
+                "Texture rows can not be read in order after seeking"
+            ));
+        }
+
+        if self.rows_read >= self.header.height {
+            return Err(anyhow!("All texture rows have already been read"));
+        }
+
+        if row.len() != self.header.stride as usize {
+            return Err(anyhow!(
+                "Row buffer is {} bytes long, texture stride is {}",
+                row.len(),
+                self.header.stride
+            ));
+        }
+
+        let result = if self.header.flags & TEXTURE_FLAG_RLE != 0 {
+            rle::decode_row(&mut self.reader, row)
+        } else {
+            self.reader.read_exact(row)
+        };
+
+        result.map_err(|err| anyhow!("Texture row {} is unreadable: {}", self.rows_read, err))?;
+
+        self.crc = crc32_update(self.crc, row);
+        self.rows_read += 1;
+
+        Ok(())
+    }
+
+    /// Checks that every row was read, matched the CRC and that nothing follows the last one,
+    /// returning the inner reader.
+    pub fn finish(mut self) -> anyhow::Result<R> {
+        if self.rows_read != self.header.height {
+            return Err(anyhow!(
+                "Only {} of {} texture rows were read",
+                self.rows_read,
+                self.header.height
+            ));
+        }
+
+        if !self.crc != self.header.crc {
+            return Err(anyhow!("Texture pixel data does not match its CRC"));
+        }
+
+        if self.reader.read(&mut [0])? != 0 {
+            return Err(anyhow!("Texture has trailing data after its last row"));
+        }
+
+        Ok(self.reader)
+    }
+}
+
+impl<R: Read + Seek> TextureReader<R> {
+    /// Reads `span.len()` bytes of row `y` starting at byte `first_byte`. Uncompressed
+    /// textures seek straight to the span, compressed ones decode the rows in between
+    /// through a single row of scratch memory. The CRC is not checked for spans.
+    pub fn read_row_span(
+        &mut self,
+        y: u16,
+        first_byte: usize,
+        span: &mut [u8],
+    ) -> anyhow::Result<()> {
+        let stride = self.header.stride as usize;
+
+        if y >= self.header.height || first_byte + span.len() > stride {
+            return Err(anyhow!("Span of row {} is outside of the texture", y));
+        }
+
+        self.seeked = true;
+
+        if self.header.flags & TEXTURE_FLAG_RLE == 0 {
+            let offset = TEXTURE_HEADER_LEN + y as usize * stride + first_byte;
+
+            self.reader.seek(SeekFrom::Start(offset as u64))?;
+            self.reader
+                .read_exact(span)
+                .map_err(|err| anyhow!("Texture row {} is unreadable: {}", y, err))?;
+
+            return Ok(());
+        }
+
+        if self.scratch_row != Some(y) {
+            if y < self.rows_read {
+                self.reader
+                    .seek(SeekFrom::Start(TEXTURE_HEADER_LEN as u64))?;
+                self.rows_read = 0;
+            }
+
+            self.scratch.resize(stride, 0);
+
+            while self.rows_read <= y {
+                rle::decode_row(&mut self.reader, &mut self.scratch).map_err(|err| {
+                    anyhow!("Texture row {} is unreadable: {}", self.rows_read, err)
+                })?;
+                self.rows_read += 1;
+            }
+
+            self.scratch_row = Some(y);
+        }
+
+        span.copy_from_slice(&self.scratch[first_byte..first_byte + span.len()]);
+
+        Ok(())
+    }
+}
+
+pub fn load_texture(file_path: &str) -> anyhow::Result<Texture> {
+    let bytes = std::fs::read(file_path)?;
+    Texture::parse(&bytes).map_err(|err| anyhow!("Invalid texture {}: {}", file_path, err))
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    !crc32_update(!0, data)
+}
+
+/// Feeds `data` into a running CRC-32 (IEEE) state, start with `!0` and invert the result.
+pub fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkerboard() -> Texture {
+        let data = (0..3 * 4)
+            .map(|i| if i % 2 == 0 { 0x55 } else { 0xAA })
+            .collect();
+
+        Texture::new(20, 4, data).unwrap()
+    }
+
+    #[test]
+    fn header_round_trips() {
+        let header = checkerboard().header;
+
+        assert_eq!(TextureHeader::parse(&header.to_bytes()).unwrap(), header);
+    }
+
+    #[test]
+    fn stride_fits_the_widest_texture() {
+        assert_eq!(TextureHeader::new(u16::MAX, 1).stride, 8192);
+        assert_eq!(TextureHeader::new(9, 1).stride, 2);
+        assert_eq!(TextureHeader::new(0, 1).stride, 0);
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        let bytes = checkerboard().header.to_bytes();
+        let with = |offset: usize, value: u8| {
+            let mut bytes = bytes;
+            bytes[offset] = value;
+            TextureHeader::parse(&bytes)
+        };
+
+        assert!(TextureHeader::parse(&bytes[..TEXTURE_HEADER_LEN - 1]).is_err());
+        assert!(with(0, b'X').is_err());
+        assert!(with(4, TEXTURE_VERSION + 1).is_err());
+        assert!(with(5, 0x80).is_err());
+        // A stride of 2 bytes holds only 16 of the 20 pixels.
+        assert!(with(10, 2).is_err());
+    }
+
+    #[test]
+    fn parses_raw_and_compressed_textures() {
+        let texture = checkerboard();
+
+        for bytes in [texture.to_bytes(), texture.to_rle_bytes()] {
+            let parsed = Texture::parse(&bytes).unwrap();
+
+            assert_eq!(parsed.header, texture.header);
+            assert_eq!(parsed.data, texture.data);
+        }
+    }
+
+    #[test]
+    fn rejects_pixel_data_that_does_not_match_the_crc() {
+        let texture = checkerboard();
+        let mut raw = texture.to_bytes();
+        let mut rle = texture.to_rle_bytes();
+
+        raw[TEXTURE_HEADER_LEN] ^= 1;
+        // The first row is a literal run, flip a pixel after its control byte.
+        rle[TEXTURE_HEADER_LEN + 1] ^= 1;
+
+        assert!(Texture::parse(&raw).is_err());
+        assert!(Texture::parse(&rle).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_and_trailing_data() {
+        let bytes = checkerboard().to_bytes();
+        let mut longer = bytes.clone();
+        longer.push(0);
+
+        assert!(Texture::parse(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Texture::parse(&longer).is_err());
+    }
+
+    #[test]
+    fn finish_requires_every_row() {
+        let bytes = checkerboard().to_bytes();
+        let mut reader = TextureReader::new(&bytes[..]).unwrap();
+        let mut row = [0; 3];
+
+        reader.read_row(&mut row).unwrap();
+
+        assert!(reader.finish().is_err());
+    }
+
+    #[test]
+    fn finish_rejects_trailing_data() {
+        let mut bytes = checkerboard().to_rle_bytes();
+        bytes.push(0);
+
+        let mut reader = TextureReader::new(&bytes[..]).unwrap();
+        let mut row = [0; 3];
+
+        for _ in 0..4 {
+            reader.read_row(&mut row).unwrap();
+        }
+
+        assert!(reader.finish().is_err());
+    }
+
+    #[test]
+    fn crc_matches_the_ieee_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+
+use super::{load_image, Texture};
+
+struct CacheEntry {
+    texture: Texture,
+    last_used: u64,
+}
+
+/// Keeps decoded textures in memory, keyed by path, so frequently drawn assets are not read
+/// from flash every frame. When the cached pixel data would exceed `budget` bytes the least
+/// recently used textures are dropped.
+///
+/// Textures are ordinary heap allocations. With `CONFIG_SPIRAM_USE_MALLOC` enabled ESP-IDF
+/// places large allocations in PSRAM, which lets the budget grow well past internal RAM.
+pub struct TextureCache {
+    budget: usize,
+    used: usize,
+    clock: u64,
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl TextureCache {
+    pub fn new(budget: usize) -> Self {
+        TextureCache {
+            budget,
+            used: 0,
+            clock: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// Returns the texture at `path`, loading it (and evicting others) if it is not cached.
+    pub fn get(&mut self, path: &str) -> anyhow::Result<&Texture> {
+        self.preload(path)?;
+        self.clock += 1;
+
+        let entry = self
+            .entries
+            .get_mut(path)
+            .ok_or_else(|| anyhow!("Texture {} was evicted while loading", path))?;
+        entry.last_used = self.clock;
+
+        Ok(&entry.texture)
+    }
+
+    /// Loads the texture at `path` into the cache without drawing it.
+    pub fn preload(&mut self, path: &str) -> anyhow::Result<()> {
+        if self.entries.contains_key(path) {
+            return Ok(());
+        }
+
+        let texture = load_image(path)?;
+        let size = texture.data.len();
+
+        if size > self.budget {
+            return Err(anyhow!(
+                "Texture {} is {} bytes, more than the whole cache budget of {}",
+                path,
+                size,
+                self.budget
+            ));
+        }
+
+        while self.used + size > self.budget {
+            self.evict_least_recently_used();
+        }
+
+        self.clock += 1;
+        self.used += size;
+        self.entries.insert(
+            path.to_owned(),
+            CacheEntry {
+                texture,
+                last_used: self.clock,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Drops the texture at `path`, returns false if it was not cached.
+    pub fn unload(&mut self, path: &str) -> bool {
+        match self.entries.remove(path) {
+            Some(entry) => {
+                self.used -= entry.texture.data.len();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used = 0;
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(path, _)| path.clone());
+
+        if let Some(path) = oldest {
+            self.unload(&path);
+        }
+    }
+}
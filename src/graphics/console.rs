@@ -0,0 +1,476 @@
+use std::fmt;
+
+use super::{Font, GlcdFont, Print, Rect, Vect2D};
+
+const TAB_WIDTH: u16 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub chr: char,
+    /// Drawn with the foreground and background colors swapped.
+    pub inverse: bool,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            chr: ' ',
+            inverse: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Escape {
+    None,
+    /// After `ESC`.
+    Start,
+    /// Inside a control sequence, after `ESC [`.
+    Csi,
+}
+
+/// A text terminal drawn into a region of the screen. Text is written with [`fmt::Write`],
+/// wraps at the right edge and scrolls at the bottom. Supported escape sequences are cursor
+/// movement (`CSI n A/B/C/D`, `CSI row;col H`), erasing (`CSI n J`, `CSI n K`) and inverse
+/// video (`CSI 7 m`, `CSI 27 m`, `CSI 0 m`).
+pub struct Console<'f> {
+    region: Rect,
+    font: &'f dyn Font,
+    cell_width: u16,
+    cell_height: u16,
+    columns: u16,
+    rows: u16,
+    cells: Vec<Cell>,
+    /// Rows changed since the last render.
+    dirty: Vec<bool>,
+    column: u16,
+    row: u16,
+    /// Set after writing to the last column, the next character goes to a new line.
+    pending_wrap: bool,
+    inverse: bool,
+    escape: Escape,
+    params: Vec<u16>,
+}
+
+impl<'f> Console<'f> {
+    /// A console using the built-in 6x8 font, 66x30 characters on the full 400x240 screen.
+    pub fn new(region: Rect) -> Console<'static> {
+        Console::with_font(region, &GlcdFont)
+    }
+
+    /// The character cell is as wide as the advance of `M` and as tall as the font's line, so
+    /// proportional fonts are drawn on a fixed grid.
+    pub fn with_font(region: Rect, font: &'f dyn Font) -> Self {
+        let cell_width = font
+            .glyph('M')
+            .map_or(1, |glyph| glyph.metrics.advance)
+            .max(1);
+        let cell_height = font.line_height().max(1);
+        let columns = (region.width / cell_width).max(1);
+        let rows = (region.height / cell_height).max(1);
+
+        Console {
+            region,
+            font,
+            cell_width,
+            cell_height,
+            columns,
+            rows,
+            cells: vec![Cell::default(); columns as usize * rows as usize],
+            dirty: vec![true; rows as usize],
+            column: 0,
+            row: 0,
+            pending_wrap: false,
+            inverse: false,
+            escape: Escape::None,
+            params: Vec::new(),
+        }
+    }
+
+    pub fn columns(&self) -> u16 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    pub fn region(&self) -> Rect {
+        self.region
+    }
+
+    /// Column and row of the cursor.
+    pub fn cursor(&self) -> (u16, u16) {
+        (self.column, self.row)
+    }
+
+    pub fn set_cursor(&mut self, column: u16, row: u16) {
+        self.column = column.min(self.columns - 1);
+        self.row = row.min(self.rows - 1);
+        self.pending_wrap = false;
+    }
+
+    pub fn set_inverse(&mut self, inverse: bool) {
+        self.inverse = inverse;
+    }
+
+    pub fn cell(&self, column: u16, row: u16) -> Option<Cell> {
+        if column >= self.columns || row >= self.rows {
+            return None;
+        }
+
+        Some(self.cells[self.index(column, row)])
+    }
+
+    /// Text of a row with trailing spaces removed.
+    pub fn line(&self, row: u16) -> String {
+        let start = self.index(0, row.min(self.rows - 1));
+        let line: String = self.cells[start..start + self.columns as usize]
+            .iter()
+            .map(|cell| cell.chr)
+            .collect();
+
+        line.trim_end().to_string()
+    }
+
+    /// Erases everything and moves the cursor to the top left corner.
+    pub fn clear(&mut self) {
+        self.cells.fill(Cell::default());
+        self.mark_dirty();
+        self.set_cursor(0, 0);
+    }
+
+    /// Moves the contents up by `lines`, clearing the rows at the bottom.
+    pub fn scroll(&mut self, lines: u16) {
+        let lines = lines.min(self.rows) as usize * self.columns as usize;
+
+        self.cells.rotate_left(lines);
+
+        let len = self.cells.len();
+        self.cells[len - lines..].fill(Cell::default());
+        self.mark_dirty();
+    }
+
+    /// Forces the next [`Console::render`] to redraw every row.
+    pub fn mark_dirty(&mut self) {
+        self.dirty.fill(true);
+    }
+
+    pub fn put_char(&mut self, chr: char) {
+        match self.escape {
+            Escape::None => {}
+            Escape::Start => {
+                self.escape = if chr == '[' {
+                    Escape::Csi
+                } else {
+                    Escape::None
+                };
+                self.params.clear();
+                return;
+            }
+            Escape::Csi => {
+                self.control_sequence(chr);
+                return;
+            }
+        }
+
+        match chr {
+            '\x1b' => self.escape = Escape::Start,
+            '\n' => {
+                self.column = 0;
+                self.line_feed();
+            }
+            '\r' => {
+                self.column = 0;
+                self.pending_wrap = false;
+            }
+            '\t' => {
+                self.column = ((self.column / TAB_WIDTH + 1) * TAB_WIDTH).min(self.columns - 1);
+                self.pending_wrap = false;
+            }
+            '\x08' => {
+                self.column = self.column.saturating_sub(1);
+                self.pending_wrap = false;
+            }
+            _ if chr.is_control() => {}
+            _ => {
+                if self.pending_wrap {
+                    self.column = 0;
+                    self.line_feed();
+                }
+
+                let index = self.index(self.column, self.row);
+                self.cells[index] = Cell {
+                    chr,
+                    inverse: self.inverse,
+                };
+                self.dirty[self.row as usize] = true;
+
+                if self.column + 1 < self.columns {
+                    self.column += 1;
+                } else {
+                    self.pending_wrap = true;
+                }
+            }
+        }
+    }
+
+    /// Draws the rows changed since the last call, each cell painted in `background` with
+    /// the character in `foreground`, or the other way around for inverse cells.
+    pub fn render<U, T>(
+        &mut self,
+        target: &mut U,
+        foreground: T,
+        background: T,
+    ) -> anyhow::Result<()>
+    where
+        U: Print<T>,
+        T: Clone,
+    {
+        for row in 0..self.rows {
+            if !self.dirty[row as usize] {
+                continue;
+            }
+
+            for column in 0..self.columns {
+                let cell = self.cells[self.index(column, row)];
+                let (ink, paper) = if cell.inverse {
+                    (background.clone(), foreground.clone())
+                } else {
+                    (foreground.clone(), background.clone())
+                };
+                let x = self.region.position.x + column * self.cell_width;
+                let y = self.region.position.y + row * self.cell_height;
+
+                target.fill_background(
+                    &Rect::new(Vect2D::new(x, y), self.cell_width, self.cell_height),
+                    paper,
+                )?;
+
+                if cell.chr == ' ' {
+                    continue;
+                }
+
+                let glyph = match self
+                    .font
+                    .glyph(cell.chr)
+                    .or_else(|| self.font.replacement_glyph())
+                {
+                    Some(glyph) => glyph,
+                    None => continue,
+                };
+                let left = x as i32 + glyph.metrics.x_offset as i32;
+                let top = y as i32 + self.font.ascent() as i32 - glyph.metrics.y_offset as i32;
+
+                if left >= 0 && top >= 0 {
+                    target.put_glyph(&Vect2D::new(left as u16, top as u16), &glyph, ink, None)?;
+                }
+            }
+
+            self.dirty[row as usize] = false;
+        }
+
+        Ok(())
+    }
+
+    fn index(&self, column: u16, row: u16) -> usize {
+        row as usize * self.columns as usize + column as usize
+    }
+
+    fn line_feed(&mut self) {
+        self.pending_wrap = false;
+
+        if self.row + 1 < self.rows {
+            self.row += 1;
+        } else {
+            self.scroll(1);
+        }
+    }
+
+    /// Erases the cells from `start` up to, but not including, `end`, counted row by row.
+    fn erase(&mut self, start: usize, end: usize) {
+        let end = end.min(self.cells.len());
+
+        if start >= end {
+            return;
+        }
+
+        self.cells[start..end].fill(Cell::default());
+
+        let columns = self.columns as usize;
+        for row in start / columns..=(end - 1) / columns {
+            self.dirty[row] = true;
+        }
+    }
+
+    fn control_sequence(&mut self, chr: char) {
+        match chr {
+            '0'..='9' => {
+                let digit = chr as u16 - '0' as u16;
+
+                match self.params.last_mut() {
+                    Some(param) => *param = param.saturating_mul(10).saturating_add(digit),
+                    None => self.params.push(digit),
+                }
+
+                return;
+            }
+            ';' => {
+                if self.params.is_empty() {
+                    self.params.push(0);
+                }
+
+                self.params.push(0);
+                return;
+            }
+            // Private sequences like `CSI ? 25 l` are parsed and ignored.
+            '?' => return,
+            _ => {}
+        }
+
+        self.escape = Escape::None;
+
+        let params = std::mem::take(&mut self.params);
+        let param = |index: usize, default: u16| match params.get(index) {
+            Some(0) | None => default,
+            Some(&value) => value,
+        };
+        let count = param(0, 1);
+        let cursor = self.index(self.column, self.row);
+
+        match chr {
+            'A' => self.set_cursor(self.column, self.row.saturating_sub(count)),
+            'B' => self.set_cursor(self.column, self.row.saturating_add(count)),
+            'C' => self.set_cursor(self.column.saturating_add(count), self.row),
+            'D' => self.set_cursor(self.column.saturating_sub(count), self.row),
+            'H' | 'f' => {
+                let (row, column) = (param(0, 1), param(1, 1));
+                self.set_cursor(column - 1, row - 1);
+            }
+            'J' => match params.first().copied().unwrap_or(0) {
+                0 => self.erase(cursor, self.cells.len()),
+                1 => self.erase(0, cursor + 1),
+                _ => self.erase(0, self.cells.len()),
+            },
+            'K' => {
+                let start = self.index(0, self.row);
+                let end = start + self.columns as usize;
+
+                match params.first().copied().unwrap_or(0) {
+                    0 => self.erase(cursor, end),
+                    1 => self.erase(start, cursor + 1),
+                    _ => self.erase(start, end),
+                }
+            }
+            'm' => {
+                if params.is_empty() {
+                    self.inverse = false;
+                }
+
+                for &param in params.iter() {
+                    match param {
+                        0 | 27 => self.inverse = false,
+                        7 => self.inverse = true,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Write for Console<'_> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        for chr in text.chars() {
+            self.put_char(chr);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+
+    /// A 5x3 console in the built-in font, with `text` written to it.
+    fn console(text: &str) -> Console<'static> {
+        let mut console = Console::new(Rect::new(Vect2D::new(0, 0), 30, 24));
+
+        console.write_str(text).unwrap();
+        console
+    }
+
+    fn lines(console: &Console) -> Vec<String> {
+        (0..console.rows()).map(|row| console.line(row)).collect()
+    }
+
+    /// Every cell filled, with the cursor moved to the third column of the second row.
+    const FULL: &str = "abcdefghijklmno\x1b[2;3H";
+
+    #[test]
+    fn positions_the_cursor() {
+        assert_eq!(console("\x1b[2;3H").cursor(), (2, 1));
+        assert_eq!(console("abc\x1b[H").cursor(), (0, 0));
+        assert_eq!(console("\x1b[9;9H").cursor(), (4, 2));
+        assert_eq!(console("\x1b[3;3H\x1b[2A\x1b[C").cursor(), (3, 0));
+    }
+
+    #[test]
+    fn erases_the_display() {
+        let erase = |mode: &str| lines(&console(&format!("{}\x1b[{}J", FULL, mode)));
+
+        assert_eq!(erase(""), ["abcde", "fg", ""]);
+        assert_eq!(erase("0"), ["abcde", "fg", ""]);
+        assert_eq!(erase("1"), ["", "   ij", "klmno"]);
+        assert_eq!(erase("2"), ["", "", ""]);
+    }
+
+    #[test]
+    fn erases_the_line() {
+        let erase = |mode: &str| lines(&console(&format!("{}\x1b[{}K", FULL, mode)));
+
+        assert_eq!(erase("0"), ["abcde", "fg", "klmno"]);
+        assert_eq!(erase("1"), ["abcde", "   ij", "klmno"]);
+        assert_eq!(erase("2"), ["abcde", "", "klmno"]);
+    }
+
+    #[test]
+    fn switches_inverse_video() {
+        let console = console("a\x1b[7mb\x1b[27mc\x1b[7md\x1b[0me\x1b[7mf\x1b[mg");
+        let inverse: Vec<bool> = [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (0, 1), (1, 1)]
+            .iter()
+            .map(|&(column, row)| console.cell(column, row).unwrap().inverse)
+            .collect();
+
+        assert_eq!(inverse, [false, true, false, true, false, true, false]);
+    }
+
+    #[test]
+    fn wraps_after_the_last_column() {
+        let mut console = console("abcde");
+
+        assert_eq!(console.cursor(), (4, 0));
+
+        console.write_str("f").unwrap();
+        assert_eq!(lines(&console), ["abcde", "f", ""]);
+
+        // A newline right after filling a row does not leave an empty one.
+        let console = self::console("abcde\nf");
+        assert_eq!(lines(&console), ["abcde", "f", ""]);
+    }
+
+    #[test]
+    fn scrolls_at_the_bottom() {
+        let console = console("1\n2\n3\n4");
+
+        assert_eq!(lines(&console), ["2", "3", "4"]);
+        assert_eq!(console.cursor(), (1, 2));
+
+        let console = self::console("abcdefghijklmnop");
+        assert_eq!(lines(&console), ["fghij", "klmno", "p"]);
+    }
+}
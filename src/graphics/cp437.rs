@@ -0,0 +1,175 @@
+/// Code page 437 characters outside of printable ASCII, sorted by code point, with their
+/// index in `GLCD_FONT`.
+const CP437: [(char, u8); 160] = [
+    ('\u{00A0}', 0xFF),
+    ('¡', 0xAD),
+    ('¢', 0x9B),
+    ('£', 0x9C),
+    ('¥', 0x9D),
+    ('§', 0x15),
+    ('ª', 0xA6),
+    ('«', 0xAE),
+    ('¬', 0xAA),
+    ('°', 0xF8),
+    ('±', 0xF1),
+    ('²', 0xFD),
+    ('µ', 0xE6),
+    ('¶', 0x14),
+    ('·', 0xFA),
+    ('º', 0xA7),
+    ('»', 0xAF),
+    ('¼', 0xAC),
+    ('½', 0xAB),
+    ('¿', 0xA8),
+    ('Ä', 0x8E),
+    ('Å', 0x8F),
+    ('Æ', 0x92),
+    ('Ç', 0x80),
+    ('É', 0x90),
+    ('Ñ', 0xA5),
+    ('Ö', 0x99),
+    ('Ü', 0x9A),
+    ('ß', 0xE1),
+    ('à', 0x85),
+    ('á', 0xA0),
+    ('â', 0x83),
+    ('ä', 0x84),
+    ('å', 0x86),
+    ('æ', 0x91),
+    ('ç', 0x87),
+    ('è', 0x8A),
+    ('é', 0x82),
+    ('ê', 0x88),
+    ('ë', 0x89),
+    ('ì', 0x8D),
+    ('í', 0xA1),
+    ('î', 0x8C),
+    ('ï', 0x8B),
+    ('ñ', 0xA4),
+    ('ò', 0x95),
+    ('ó', 0xA2),
+    ('ô', 0x93),
+    ('ö', 0x94),
+    ('÷', 0xF6),
+    ('ù', 0x97),
+    ('ú', 0xA3),
+    ('û', 0x96),
+    ('ü', 0x81),
+    ('ÿ', 0x98),
+    ('ƒ', 0x9F),
+    ('Γ', 0xE2),
+    ('Θ', 0xE9),
+    ('Σ', 0xE4),
+    ('Φ', 0xE8),
+    ('Ω', 0xEA),
+    ('α', 0xE0),
+    ('δ', 0xEB),
+    ('ε', 0xEE),
+    ('π', 0xE3),
+    ('σ', 0xE5),
+    ('τ', 0xE7),
+    ('φ', 0xED),
+    ('•', 0x07),
+    ('‼', 0x13),
+    ('ⁿ', 0xFC),
+    ('₧', 0x9E),
+    ('←', 0x1B),
+    ('↑', 0x18),
+    ('→', 0x1A),
+    ('↓', 0x19),
+    ('↔', 0x1D),
+    ('↕', 0x12),
+    ('↨', 0x17),
+    ('∙', 0xF9),
+    ('√', 0xFB),
+    ('∞', 0xEC),
+    ('∟', 0x1C),
+    ('∩', 0xEF),
+    ('≈', 0xF7),
+    ('≡', 0xF0),
+    ('≤', 0xF3),
+    ('≥', 0xF2),
+    ('⌂', 0x7F),
+    ('⌐', 0xA9),
+    ('⌠', 0xF4),
+    ('⌡', 0xF5),
+    ('─', 0xC4),
+    ('│', 0xB3),
+    ('┌', 0xDA),
+    ('┐', 0xBF),
+    ('└', 0xC0),
+    ('┘', 0xD9),
+    ('├', 0xC3),
+    ('┤', 0xB4),
+    ('┬', 0xC2),
+    ('┴', 0xC1),
+    ('┼', 0xC5),
+    ('═', 0xCD),
+    ('║', 0xBA),
+    ('╒', 0xD5),
+    ('╓', 0xD6),
+    ('╔', 0xC9),
+    ('╕', 0xB8),
+    ('╖', 0xB7),
+    ('╗', 0xBB),
+    ('╘', 0xD4),
+    ('╙', 0xD3),
+    ('╚', 0xC8),
+    ('╛', 0xBE),
+    ('╜', 0xBD),
+    ('╝', 0xBC),
+    ('╞', 0xC6),
+    ('╟', 0xC7),
+    ('╠', 0xCC),
+    ('╡', 0xB5),
+    ('╢', 0xB6),
+    ('╣', 0xB9),
+    ('╤', 0xD1),
+    ('╥', 0xD2),
+    ('╦', 0xCB),
+    ('╧', 0xCF),
+    ('╨', 0xD0),
+    ('╩', 0xCA),
+    ('╪', 0xD8),
+    ('╫', 0xD7),
+    ('╬', 0xCE),
+    ('▀', 0xDF),
+    ('▄', 0xDC),
+    ('█', 0xDB),
+    ('▌', 0xDD),
+    ('▐', 0xDE),
+    ('░', 0xB0),
+    ('▒', 0xB1),
+    ('▓', 0xB2),
+    ('■', 0xFE),
+    ('▬', 0x16),
+    ('▲', 0x1E),
+    ('►', 0x10),
+    ('▼', 0x1F),
+    ('◄', 0x11),
+    ('○', 0x09),
+    ('◘', 0x08),
+    ('◙', 0x0A),
+    ('☺', 0x01),
+    ('☻', 0x02),
+    ('☼', 0x0F),
+    ('♀', 0x0C),
+    ('♂', 0x0B),
+    ('♠', 0x06),
+    ('♣', 0x05),
+    ('♥', 0x03),
+    ('♦', 0x04),
+    ('♪', 0x0D),
+    ('♫', 0x0E),
+];
+
+/// Maps a character to its index in the code page 437 ordered `GLCD_FONT` table.
+pub fn cp437_index(chr: char) -> Option<u8> {
+    match chr {
+        ' '..='~' => Some(chr as u8),
+        _ => CP437
+            .binary_search_by_key(&chr, |&(chr, _)| chr)
+            .ok()
+            .map(|index| CP437[index].1),
+    }
+}
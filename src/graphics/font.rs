@@ -0,0 +1,228 @@
+use std::borrow::Cow;
+
+use super::cp437::cp437_index;
+use super::glcdfont::{GLCD_EXTENDED, GLCD_FONT, GLCD_REPLACEMENT};
+use super::Rotation;
+
+/// Size and placement of a glyph bitmap relative to the pen, which sits on the baseline.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GlyphMetrics {
+    pub width: u16,
+    pub height: u16,
+    /// Horizontal distance from the pen to the left edge of the bitmap.
+    pub x_offset: i16,
+    /// Vertical distance from the baseline up to the top edge of the bitmap.
+    pub y_offset: i16,
+    /// How far the pen moves after drawing the glyph.
+    pub advance: u16,
+}
+
+impl GlyphMetrics {
+    /// Bytes in the glyph's bitmap, rows of `ceil(width / 8)` bytes.
+    pub fn bitmap_len(&self) -> usize {
+        (self.width as usize + 7) / 8 * self.height as usize
+    }
+}
+
+/// A glyph bitmap stored as rows of `ceil(width / 8)` bytes, least significant bit first.
+/// Set bits are drawn in the text color.
+pub struct Glyph<'a> {
+    pub metrics: GlyphMetrics,
+    pub bitmap: Cow<'a, [u8]>,
+}
+
+impl Glyph<'_> {
+    pub fn stride(&self) -> usize {
+        (self.metrics.width as usize + 7) / 8
+    }
+
+    pub fn is_set(&self, x: u16, y: u16) -> bool {
+        self.bitmap[y as usize * self.stride() + (x / 8) as usize] & (1 << (x % 8)) != 0
+    }
+}
+
+/// Integer magnification followed by a rotation about the glyph's top left corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlyphTransform {
+    pub scale: u16,
+    pub rotation: Rotation,
+}
+
+impl Default for GlyphTransform {
+    fn default() -> Self {
+        GlyphTransform {
+            scale: 1,
+            rotation: Rotation::Deg0,
+        }
+    }
+}
+
+impl GlyphTransform {
+    /// Width and height of a transformed bitmap.
+    pub fn size(&self, metrics: &GlyphMetrics) -> (u16, u16) {
+        let width = metrics.width.saturating_mul(self.scale.max(1));
+        let height = metrics.height.saturating_mul(self.scale.max(1));
+
+        match self.rotation {
+            Rotation::Deg0 | Rotation::Deg180 => (width, height),
+            Rotation::Deg90 | Rotation::Deg270 => (height, width),
+        }
+    }
+
+    /// The glyph pixel shown at (`x`, `y`) of the transformed bitmap.
+    pub fn source(&self, metrics: &GlyphMetrics, x: u16, y: u16) -> (u16, u16) {
+        let scale = self.scale.max(1);
+        let width = metrics.width.saturating_mul(scale);
+        let height = metrics.height.saturating_mul(scale);
+        let (u, v) = match self.rotation {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (y, height - 1 - x),
+            Rotation::Deg180 => (width - 1 - x, height - 1 - y),
+            Rotation::Deg270 => (width - 1 - y, x),
+        };
+
+        (u / scale, v / scale)
+    }
+}
+
+pub trait Font {
+    /// Pixels from the top of a line to the baseline.
+    fn ascent(&self) -> u16;
+
+    /// Pixels from the baseline to the bottom of a line.
+    fn descent(&self) -> u16;
+
+    fn line_height(&self) -> u16 {
+        self.ascent() + self.descent()
+    }
+
+    fn glyph(&self, chr: char) -> Option<Glyph<'_>>;
+
+    /// Drawn in place of characters without a glyph, `U+FFFD` or else `?`.
+    fn replacement_glyph(&self) -> Option<Glyph<'_>> {
+        self.glyph(char::REPLACEMENT_CHARACTER)
+            .or_else(|| self.glyph('?'))
+    }
+
+    /// Extra horizontal adjustment between two neighbouring characters.
+    fn kerning(&self, _left: char, _right: char) -> i16 {
+        0
+    }
+}
+
+/// The built-in fixed width 5x7 font, drawn in a 6x8 cell.
+pub struct GlcdFont;
+
+impl Font for GlcdFont {
+    fn ascent(&self) -> u16 {
+        7
+    }
+
+    fn descent(&self) -> u16 {
+        1
+    }
+
+    fn glyph(&self, chr: char) -> Option<Glyph<'_>> {
+        let columns = glcd_columns(chr)?;
+
+        // The table stores glyphs column by column, transpose them into rows.
+        let mut bitmap = vec![0u8; 8];
+
+        for (i, column) in columns.iter().enumerate() {
+            for (j, row) in bitmap.iter_mut().enumerate() {
+                if column & (1 << j) != 0 {
+                    *row |= 1 << i;
+                }
+            }
+        }
+
+        Some(Glyph {
+            metrics: GlyphMetrics {
+                width: 5,
+                height: 8,
+                x_offset: 0,
+                y_offset: 7,
+                advance: 6,
+            },
+            bitmap: Cow::Owned(bitmap),
+        })
+    }
+}
+
+/// Columns of the built-in font's glyph for `chr`, least significant bit at the top. Covers
+/// code page 437, the Latin Extended letters in `GLCD_EXTENDED` and `U+FFFD`.
+pub fn glcd_columns(chr: char) -> Option<[u8; 5]> {
+    if chr == char::REPLACEMENT_CHARACTER {
+        return Some(GLCD_REPLACEMENT);
+    }
+
+    if let Some(index) = cp437_index(chr) {
+        let start = index as usize * 5;
+        let mut columns = [0; 5];
+
+        columns.copy_from_slice(&GLCD_FONT[start..start + 5]);
+        return Some(columns);
+    }
+
+    GLCD_EXTENDED
+        .binary_search_by_key(&chr, |&(chr, _)| chr)
+        .ok()
+        .map(|index| GLCD_EXTENDED[index].1)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BitmapGlyph {
+    pub chr: char,
+    pub metrics: GlyphMetrics,
+    /// Offset of the glyph's rows in [`BitmapFont::bitmaps`].
+    pub offset: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KerningPair {
+    pub left: char,
+    pub right: char,
+    pub adjust: i16,
+}
+
+/// A proportional font made of pre-rendered glyph bitmaps. The tables can be `const` data
+/// compiled into the firmware or loaded at runtime. `glyphs` must be sorted by character and
+/// `kerning` by `(left, right)`.
+pub struct BitmapFont<'a> {
+    pub ascent: u16,
+    pub descent: u16,
+    pub glyphs: Cow<'a, [BitmapGlyph]>,
+    pub bitmaps: Cow<'a, [u8]>,
+    pub kerning: Cow<'a, [KerningPair]>,
+}
+
+impl Font for BitmapFont<'_> {
+    fn ascent(&self) -> u16 {
+        self.ascent
+    }
+
+    fn descent(&self) -> u16 {
+        self.descent
+    }
+
+    fn glyph(&self, chr: char) -> Option<Glyph<'_>> {
+        let glyph = self
+            .glyphs
+            .binary_search_by_key(&chr, |glyph| glyph.chr)
+            .ok()
+            .map(|index| &self.glyphs[index])?;
+        let start = glyph.offset as usize;
+        let end = start.checked_add(glyph.metrics.bitmap_len())?;
+
+        Some(Glyph {
+            metrics: glyph.metrics,
+            bitmap: Cow::Borrowed(self.bitmaps.get(start..end)?),
+        })
+    }
+
+    fn kerning(&self, left: char, right: char) -> i16 {
+        self.kerning
+            .binary_search_by_key(&(left, right), |pair| (pair.left, pair.right))
+            .map_or(0, |index| self.kerning[index].adjust)
+    }
+}
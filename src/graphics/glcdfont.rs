@@ -87,3 +87,21 @@ pub const GLCD_FONT: [u8; 1280] = [
     0x01, 0x01, 0x00, 0x1F, 0x01, 0x01, 0x1E, 0x00, 0x19, 0x1D, 0x17, 0x12, 0x00, 0x3C, 0x3C, 0x3C,
     0x3C, 0x00, 0x00, 0x00, 0x00, 0x00, // #255 NBSP
 ];
+
+/// Latin Extended letters missing from code page 437, in the same column layout as
+/// `GLCD_FONT` and sorted by character.
+pub const GLCD_EXTENDED: [(char, [u8; 5]); 10] = [
+    ('Ć', [0x38, 0x44, 0x46, 0x45, 0x28]),
+    ('ć', [0x38, 0x44, 0x46, 0x45, 0x20]),
+    ('Č', [0x38, 0x45, 0x46, 0x45, 0x28]),
+    ('č', [0x38, 0x45, 0x46, 0x45, 0x20]),
+    ('Đ', [0x49, 0x7F, 0x49, 0x41, 0x3E]),
+    ('đ', [0x38, 0x44, 0x46, 0x2B, 0x7F]),
+    ('Š', [0x48, 0x55, 0x56, 0x55, 0x24]),
+    ('š', [0x48, 0x55, 0x56, 0x15, 0x20]),
+    ('Ž', [0x44, 0x65, 0x56, 0x4D, 0x44]),
+    ('ž', [0x64, 0x55, 0x4E, 0x45, 0x00]),
+];
+
+/// An inverted question mark, drawn for characters the font has no glyph for.
+pub const GLCD_REPLACEMENT: [u8; 5] = [0x7D, 0x7E, 0x26, 0x76, 0x79];
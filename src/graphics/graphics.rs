@@ -1,4 +1,6 @@
-#[derive(Clone, Copy)]
+use super::{Glyph, GlyphTransform};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Vect2D {
     pub x: u16,
     pub y: u16,
@@ -10,28 +12,165 @@ impl Vect2D {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub position: Vect2D,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Rect {
+    pub fn new(position: Vect2D, width: u16, height: u16) -> Self {
+        Rect {
+            position,
+            width,
+            height,
+        }
+    }
+
+    pub fn contains(&self, point: Vect2D) -> bool {
+        point.x >= self.position.x
+            && point.y >= self.position.y
+            && (point.x - self.position.x) < self.width
+            && (point.y - self.position.y) < self.height
+    }
+
+    /// The area covered by both rectangles, `None` if they do not overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let left = self.position.x.max(other.position.x);
+        let top = self.position.y.max(other.position.y);
+        let right = (self.position.x as u32 + self.width as u32)
+            .min(other.position.x as u32 + other.width as u32);
+        let bottom = (self.position.y as u32 + self.height as u32)
+            .min(other.position.y as u32 + other.height as u32);
+
+        if right <= left as u32 || bottom <= top as u32 {
+            return None;
+        }
+
+        Some(Rect::new(
+            Vect2D::new(left, top),
+            (right - left as u32) as u16,
+            (bottom - top as u32) as u16,
+        ))
+    }
+}
+
+/// Clockwise rotation in quarter turns.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+/// Calls `plot` for every pixel of the line from `c1` to `c2`, both ends included. The same
+/// pixels are plotted whichever end comes first.
+pub fn plot_line<F>(mut c1: Vect2D, mut c2: Vect2D, mut plot: F) -> anyhow::Result<()>
+where
+    F: FnMut(Vect2D) -> anyhow::Result<()>,
+{
+    let steep = c2.y.abs_diff(c1.y) > c2.x.abs_diff(c1.x);
+
+    if steep {
+        std::mem::swap(&mut c1.x, &mut c1.y);
+        std::mem::swap(&mut c2.x, &mut c2.y);
+    }
+
+    if c1.x > c2.x {
+        std::mem::swap(&mut c1, &mut c2);
+    }
+
+    let dx = c2.x - c1.x;
+    let dy = c2.y.abs_diff(c1.y);
+    let step_y = if c1.y < c2.y { 1 } else { -1 };
+    let mut err: i32 = (dx / 2).into();
+    // The error can step past the last row after the final pixel.
+    let mut y = c1.y as i32;
+
+    for x in c1.x..=c2.x {
+        if steep {
+            plot(Vect2D::new(y as u16, x))?;
+        } else {
+            plot(Vect2D::new(x, y as u16))?;
+        }
+
+        err -= dy as i32;
+
+        if err < 0 {
+            y += step_y;
+            err += dx as i32;
+        }
+    }
+
+    Ok(())
+}
+
 pub trait SetPixel<T> {
     fn set_pixel(&mut self, c: Vect2D, color: T) -> anyhow::Result<()>;
 }
 
 pub trait Print<T> {
-    fn put_char(&mut self, c: &Vect2D, chr: char, color: T) -> anyhow::Result<()>;
+    /// Draws a character of the built-in font in a 6x8 cell. With a `background` the whole
+    /// cell is painted, without one only the character's own pixels are drawn.
+    fn put_char(
+        &mut self,
+        c: &Vect2D,
+        chr: char,
+        color: T,
+        background: Option<T>,
+    ) -> anyhow::Result<()>;
+
+    /// Draws a glyph bitmap, painting its unset pixels with `background` if there is one.
+    fn put_glyph(
+        &mut self,
+        c: &Vect2D,
+        glyph: &Glyph,
+        color: T,
+        background: Option<T>,
+    ) -> anyhow::Result<()>;
+
+    /// Draws a glyph magnified and rotated by `transform`, with `c` the top left corner of the
+    /// transformed bitmap.
+    fn put_glyph_transformed(
+        &mut self,
+        c: &Vect2D,
+        glyph: &Glyph,
+        transform: GlyphTransform,
+        color: T,
+        background: Option<T>,
+    ) -> anyhow::Result<()>;
+
+    /// Paints the area behind a line of text, clipped to the drawable area.
+    fn fill_background(&mut self, area: &Rect, color: T) -> anyhow::Result<()>;
 }
 
 pub trait Draw<T>: SetPixel<T> {
     fn clear(&mut self, color: T) -> anyhow::Result<()>;
 
+    /// Draws both ends of the line and everything between them.
     fn draw_line(&mut self, c1: Vect2D, c2: Vect2D, color: T) -> anyhow::Result<()>;
 
     fn draw_hline(&mut self, c: Vect2D, len: u16, color: T) -> anyhow::Result<()>;
 
     fn draw_vline(&mut self, c: Vect2D, height: u16, color: T) -> anyhow::Result<()>;
 
+    /// Outlines the rectangle between two opposite corners, given in any order, with both
+    /// corners on the outline.
     fn draw_rectangle(&mut self, corner1: Vect2D, corner2: Vect2D, color: T) -> anyhow::Result<()>;
 
+    /// Fills the rectangle between two opposite corners, given in any order, from the top left
+    /// one up to, but not including, the bottom right one.
     fn fill_rectangle(&mut self, corner1: Vect2D, corner2: Vect2D, color: T) -> anyhow::Result<()>;
 
+    /// Draws rows of packed pixels, least significant bit leftmost and set bits white, with
+    /// the first bit of the first row at `corner`. Rows may differ in length, the parts of
+    /// them off the drawable area are clipped.
     fn draw_texture(&mut self, corner: Vect2D, texture: &Vec<Vec<u8>>) -> anyhow::Result<()>;
 
     fn draw_texture_from_flash(&mut self, corner: Vect2D, path: &str) -> anyhow::Result<()>;
+
+    fn draw_image_from_flash(&mut self, corner: Vect2D, path: &str) -> anyhow::Result<()>;
 }
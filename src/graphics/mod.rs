@@ -1,8 +1,23 @@
+pub mod console;
+mod cp437;
+pub mod font;
 mod glcdfont;
 pub mod graphics;
 pub mod mono_graphics;
 pub mod printer;
+pub mod sprite_animation;
+pub mod text_layout;
+#[cfg(feature = "truetype")]
+pub mod truetype;
+pub mod viewport;
 
+pub use console::*;
+pub use font::*;
 pub use graphics::*;
 pub use mono_graphics::*;
 pub use printer::*;
+pub use sprite_animation::*;
+pub use text_layout::*;
+#[cfg(feature = "truetype")]
+pub use truetype::*;
+pub use viewport::*;
@@ -1,12 +1,20 @@
-use std::{borrow::BorrowMut, mem::swap};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, Write};
 
 use anyhow::anyhow;
-use esp_idf_svc::sys::abs;
 
 use crate::display::Display;
+use crate::filesystem::{
+    encode_pbm, load_image, write_screenshot_frame, Texture, TextureCache, TextureReader,
+};
 
-use super::glcdfont::GLCD_FONT;
-use super::{Draw, Print, SetPixel, Vect2D};
+use super::glcdfont::GLCD_REPLACEMENT;
+use super::{
+    glcd_columns, plot_line, Draw, Glyph, GlyphTransform, Print, Rect, Rotation, SetPixel, Vect2D,
+};
+
+// Small enough to not waste heap, large enough that compressed rows are not read byte by byte.
+const STREAM_BUFFER_LEN: usize = 256;
 
 pub const WHITE: bool = true;
 pub const BLACK: bool = false;
@@ -26,103 +34,673 @@ const CAP: [u8; 7] = [
     !0b00000001,
 ];
 
+/// How a source bit is combined with the one already in the framebuffer when blitting, set
+/// bits being white.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RasterOp {
+    /// Replaces the destination.
+    #[default]
+    Copy,
+    /// Replaces the destination with the inverted source.
+    CopyInverted,
+    /// Black source pixels are drawn, white ones are transparent.
+    And,
+    /// White source pixels are drawn, black ones are transparent.
+    Or,
+    /// Inverts the destination under white source pixels.
+    Xor,
+}
+
+impl RasterOp {
+    pub fn apply(self, source: u8, destination: u8) -> u8 {
+        match self {
+            RasterOp::Copy => source,
+            RasterOp::CopyInverted => !source,
+            RasterOp::And => source & destination,
+            RasterOp::Or => source | destination,
+            RasterOp::Xor => source ^ destination,
+        }
+    }
+}
+
+/// Drawing happens in logical coordinates, `width` x `height`, which are mirrored and then
+/// rotated onto the panel's framebuffer in `buffer`.
 pub struct MonoGraphics<'a> {
-    pub display: &'a mut (dyn Display + 'a),
+    /// The panel the framebuffer is sent to, `None` for off-screen bitmaps.
+    pub display: Option<&'a mut (dyn Display + 'a)>,
     pub buffer: Vec<Vec<u8>>,
     pub width: u16,
     pub height: u16,
+    physical_width: u16,
+    physical_height: u16,
+    rotation: Rotation,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+    inverted: bool,
+    clip: Rect,
+    /// Framebuffer rows changed since they were last sent to the display.
+    dirty: Vec<bool>,
+    /// A framebuffer row inverted on its way to the panel, kept so drawing does not allocate.
+    inverted_line: Vec<u8>,
 }
 
 impl<'a> MonoGraphics<'a> {
     pub fn new(display: &'a mut dyn Display, width: u16, height: u16) -> Self {
+        Self::with_display(Some(display), width, height)
+    }
+
+    fn with_display(display: Option<&'a mut (dyn Display + 'a)>, width: u16, height: u16) -> Self {
         MonoGraphics {
-            display: display,
-            buffer: vec![vec![0xFF; (width / 8) as usize]; height as usize],
-            width: width,
-            height: height,
+            display,
+            buffer: vec![vec![0xFF; ((width + 7) / 8) as usize]; height as usize],
+            width,
+            height,
+            physical_width: width,
+            physical_height: height,
+            rotation: Rotation::Deg0,
+            flip_horizontal: false,
+            flip_vertical: false,
+            inverted: false,
+            clip: Rect::new(Vect2D::new(0, 0), width, height),
+            dirty: vec![true; height as usize],
+            inverted_line: vec![0; ((width + 7) / 8) as usize],
         }
     }
 
-    pub fn clear_display(&mut self) -> anyhow::Result<()> {
-        self.display.clear_display()
+    /// A standalone bitmap of any size to draw into, e.g. to cache a rendered widget and
+    /// [`MonoGraphics::blit`] it to the screen. [`MonoGraphics::draw`] does nothing on it.
+    pub fn offscreen(width: u16, height: u16) -> MonoGraphics<'static> {
+        MonoGraphics::with_display(None, width, height)
     }
 
-    pub fn draw(&mut self) -> anyhow::Result<()> {
-        self.display.borrow_mut().refresh(&self.buffer)
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
     }
-}
 
-impl SetPixel<bool> for MonoGraphics<'_> {
-    fn set_pixel(&mut self, c: Vect2D, color: bool) -> anyhow::Result<()> {
-        let left: u8 = (c.x % 8) as u8;
-        let whole: u16 = (c.x - left as u16) / 8;
+    /// Rotates everything drawn from now on clockwise, `Deg90` and `Deg270` swap `width` and
+    /// `height`. What is already in the framebuffer is left as it is and the clip is reset.
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.rotation = rotation;
 
-        if color {
-            self.buffer[c.y as usize][whole as usize] |= SET[left as usize];
+        (self.width, self.height) = match rotation {
+            Rotation::Deg0 | Rotation::Deg180 => (self.physical_width, self.physical_height),
+            Rotation::Deg90 | Rotation::Deg270 => (self.physical_height, self.physical_width),
+        };
+        self.clip = self.screen();
+    }
+
+    /// Mirrors drawing left to right and top to bottom, before it is rotated.
+    pub fn set_flip(&mut self, horizontal: bool, vertical: bool) {
+        self.flip_horizontal = horizontal;
+        self.flip_vertical = vertical;
+    }
+
+    /// Framebuffer position of the logical point `c`, which must be on screen.
+    pub fn to_physical(&self, c: Vect2D) -> Vect2D {
+        let x = if self.flip_horizontal {
+            self.width - 1 - c.x
         } else {
-            self.buffer[c.y as usize][whole as usize] &= CLR[left as usize];
+            c.x
+        };
+        let y = if self.flip_vertical {
+            self.height - 1 - c.y
+        } else {
+            c.y
+        };
+
+        match self.rotation {
+            Rotation::Deg0 => Vect2D::new(x, y),
+            Rotation::Deg90 => Vect2D::new(self.physical_width - 1 - y, x),
+            Rotation::Deg180 => {
+                Vect2D::new(self.physical_width - 1 - x, self.physical_height - 1 - y)
+            }
+            Rotation::Deg270 => Vect2D::new(y, self.physical_height - 1 - x),
+        }
+    }
+
+    pub fn clip(&self) -> Rect {
+        self.clip
+    }
+
+    /// Restricts drawing to `clip`, pixels outside of it are silently skipped. `None` allows
+    /// drawing on the whole screen again.
+    pub fn set_clip(&mut self, clip: Option<Rect>) {
+        let screen = self.screen();
+
+        self.clip = match clip {
+            Some(clip) => clip.intersection(&screen).unwrap_or_default(),
+            None => screen,
+        };
+    }
+
+    fn screen(&self) -> Rect {
+        Rect::new(Vect2D::new(0, 0), self.width, self.height)
+    }
+
+    /// Whether logical coordinates are framebuffer coordinates.
+    fn is_untransformed(&self) -> bool {
+        self.rotation == Rotation::Deg0 && !self.flip_horizontal && !self.flip_vertical
+    }
+
+    /// Whether nothing is transformed or clipped, so rows can be copied whole.
+    fn can_copy_rows(&self) -> bool {
+        self.is_untransformed() && self.clip == self.screen()
+    }
+
+    pub fn inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Swaps black and white on the panel from the next [`MonoGraphics::draw`] on, e.g. for a
+    /// dark theme. The framebuffer is not touched, so nothing needs to be redrawn.
+    pub fn set_inverted(&mut self, inverted: bool) {
+        if inverted != self.inverted {
+            self.mark_dirty();
+        }
+
+        self.inverted = inverted;
+    }
+
+    /// Flips every pixel of `area` between black and white, clipped to the screen.
+    pub fn invert_region(&mut self, area: &Rect) {
+        let area = match area.intersection(&self.clip) {
+            Some(area) => area,
+            None => return,
+        };
+
+        for y in area.position.y..area.position.y + area.height {
+            for x in area.position.x..area.position.x + area.width {
+                let c = self.to_physical(Vect2D { x, y });
+
+                self.buffer[c.y as usize][(c.x / 8) as usize] ^= SET[(c.x % 8) as usize];
+                self.dirty[c.y as usize] = true;
+            }
+        }
+    }
+
+    /// Color of a pixel, `None` outside the screen.
+    pub fn pixel(&self, c: Vect2D) -> Option<bool> {
+        if c.x >= self.width || c.y >= self.height {
+            return None;
+        }
+
+        let c = self.to_physical(c);
+
+        Some(self.buffer[c.y as usize][(c.x / 8) as usize] & SET[(c.x % 8) as usize] != 0)
+    }
+
+    /// Combines the `area` of `source` into this framebuffer at `corner` using `op`. Both are
+    /// clipped, so parts of `area` outside `source` or of the result outside the screen are
+    /// skipped.
+    pub fn blit(
+        &mut self,
+        source: &MonoGraphics,
+        area: Rect,
+        corner: Vect2D,
+        op: RasterOp,
+    ) -> anyhow::Result<()> {
+        let area = match area.intersection(&source.screen()) {
+            Some(area) => area,
+            None => return Ok(()),
+        };
+        let bitmap = source.read_area(&area);
+        let stride = ((area.width + 7) / 8) as usize;
+
+        self.draw_bitmap_rows(corner, &bitmap, area.width, area.height, stride, op)
+    }
+
+    /// Shifts the contents of `area` right by `dx` and down by `dy`, negative values moving
+    /// them left and up. Pixels moved out of `area` are lost and the uncovered ones are set to
+    /// `fill`. Only the part of `area` inside the clip is scrolled.
+    pub fn scroll_region(
+        &mut self,
+        area: Rect,
+        dx: i16,
+        dy: i16,
+        fill: bool,
+    ) -> anyhow::Result<()> {
+        let area = match area.intersection(&self.clip) {
+            Some(area) => area,
+            None => return Ok(()),
+        };
+        let (distance_x, distance_y) = (dx.unsigned_abs(), dy.unsigned_abs());
+
+        if distance_x >= area.width || distance_y >= area.height {
+            return self.fill_area(&area, fill);
+        }
+
+        let (left, top) = (area.position.x, area.position.y);
+        let (width, height) = (area.width - distance_x, area.height - distance_y);
+        let source = Vect2D::new(
+            if dx < 0 { left + distance_x } else { left },
+            if dy < 0 { top + distance_y } else { top },
+        );
+        let corner = Vect2D::new(
+            if dx > 0 { left + distance_x } else { left },
+            if dy > 0 { top + distance_y } else { top },
+        );
+
+        if self.is_untransformed() && dx == 0 && left == 0 && area.width == self.width {
+            // Whole rows only change places, the uncovered ones are filled below.
+            let rows = &mut self.buffer[top as usize..(top + area.height) as usize];
+
+            if dy > 0 {
+                rows.rotate_right(distance_y as usize);
+            } else {
+                rows.rotate_left(distance_y as usize);
+            }
+
+            self.dirty[top as usize..(top + area.height) as usize].fill(true);
+        } else if self.is_untransformed() {
+            self.shift_area(&area, dx, dy);
+        } else {
+            let source = Rect::new(source, width, height);
+            let bitmap = self.read_area(&source);
+            let stride = ((width + 7) / 8) as usize;
+
+            self.draw_bitmap_rows(corner, &bitmap, width, height, stride, RasterOp::Copy)?;
+        }
+
+        let uncovered_top = if dy > 0 { top } else { top + height };
+        let uncovered_left = if dx > 0 { left } else { left + width };
+
+        self.fill_area(
+            &Rect::new(Vect2D::new(left, uncovered_top), area.width, distance_y),
+            fill,
+        )?;
+        self.fill_area(
+            &Rect::new(Vect2D::new(uncovered_left, corner.y), distance_x, height),
+            fill,
+        )
+    }
+
+    /// Moves the pixels of `area`, which must be on screen on an untransformed framebuffer, by
+    /// `dx` and `dy` in place. Pixels outside of `area` are left as they are, the uncovered
+    /// ones inside it keep stale contents until they are filled.
+    fn shift_area(&mut self, area: &Rect, dx: i16, dy: i16) {
+        let left = area.position.x as usize;
+        let right = (area.position.x + area.width) as usize;
+        let (first, last) = (left / 8, (right - 1) / 8);
+        // Bits of a byte that are inside `area`, the others are put back after shifting.
+        let inside = |byte: usize| {
+            let low = left.max(byte * 8) - byte * 8;
+            let high = right.min(byte * 8 + 8) - byte * 8;
+
+            ((1u16 << high) - (1u16 << low)) as u8
+        };
+        let (first_mask, last_mask) = (inside(first), inside(last));
+        let distance_y = dy.unsigned_abs();
+        let top = area.position.y as usize;
+
+        for i in 0..(area.height - distance_y) as usize {
+            // Rows moving down are done from the bottom, so none is overwritten before it is
+            // read.
+            let i = if dy > 0 {
+                (area.height - distance_y) as usize - 1 - i
+            } else {
+                i
+            };
+            let source = if dy < 0 {
+                top + distance_y as usize
+            } else {
+                top
+            } + i;
+            let destination = if dy > 0 {
+                top + distance_y as usize
+            } else {
+                top
+            } + i;
+            let edges = (
+                self.buffer[destination][first],
+                self.buffer[destination][last],
+            );
+
+            if source != destination {
+                let (low, high) = self.buffer.split_at_mut(source.max(destination));
+                let (from, to) = if source < destination {
+                    (&low[source], &mut high[0])
+                } else {
+                    (&high[0], &mut low[destination])
+                };
+
+                to[first..=last].copy_from_slice(&from[first..=last]);
+            }
+
+            let line = &mut self.buffer[destination];
+
+            shift_bits(&mut line[first..=last], dx);
+            line[first] = (line[first] & first_mask) | (edges.0 & !first_mask);
+            line[last] = (line[last] & last_mask) | (edges.1 & !last_mask);
+            self.dirty[destination] = true;
+        }
+    }
+
+    /// Forces the next [`MonoGraphics::draw_dirty`] to send every row, e.g. after writing to
+    /// `buffer` directly.
+    pub fn mark_dirty(&mut self) {
+        self.dirty.fill(true);
+    }
+
+    /// Sends only the rows changed since the last draw, which is much faster than
+    /// [`MonoGraphics::draw`] when little has changed.
+    pub fn draw_dirty(&mut self) -> anyhow::Result<()> {
+        let display = match self.display.as_deref_mut() {
+            Some(display) => display,
+            None => return Ok(()),
+        };
+
+        for row in 0..self.buffer.len() {
+            if !self.dirty[row] {
+                continue;
+            }
+
+            if self.inverted {
+                invert_line(&self.buffer[row], &mut self.inverted_line);
+                display.refresh_line(line_num(row)?, &self.inverted_line)?;
+            } else {
+                display.refresh_line(line_num(row)?, &self.buffer[row])?;
+            }
+
+            self.dirty[row] = false;
         }
 
         Ok(())
     }
-}
 
-impl Draw<bool> for MonoGraphics<'_> {
-    fn clear(&mut self, color: bool) -> anyhow::Result<()> {
-        let line_color = if color { 0xFF } else { 0x00 };
+    /// The screen as it looks on the panel, upright and with the global invert applied, as a
+    /// binary PBM file.
+    pub fn screenshot(&self) -> Vec<u8> {
+        let mut rows = self.read_area(&self.screen());
 
-        for i in 0..self.buffer.len() {
-            self.buffer[i].fill(line_color);
+        if self.inverted {
+            rows.iter_mut().for_each(|byte| *byte = !*byte);
+        }
+
+        encode_pbm(self.width, self.height, &rows)
+    }
+
+    /// Saves a screenshot, e.g. to `/spiffs/screen.pbm`.
+    pub fn save_screenshot(&self, path: &str) -> anyhow::Result<()> {
+        std::fs::write(path, self.screenshot())
+            .map_err(|err| anyhow!("Could not save screenshot to {}: {}", path, err))
+    }
+
+    /// Prints a screenshot framed in text to `writer`, usually the serial console on stdout.
+    /// The `screenshot` tool turns a saved monitor log into PNG files.
+    pub fn send_screenshot<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        write_screenshot_frame(writer, &self.screenshot())
+    }
+
+    /// Packs `area`, which must be on screen, into rows of `ceil(width / 8)` bytes.
+    fn read_area(&self, area: &Rect) -> Vec<u8> {
+        let stride = ((area.width + 7) / 8) as usize;
+        let mut bitmap = vec![0; stride * area.height as usize];
+
+        for y in 0..area.height {
+            let row = &mut bitmap[y as usize * stride..(y as usize + 1) * stride];
+            let source_y = area.position.y + y;
+
+            if self.is_untransformed() {
+                let line = &self.buffer[source_y as usize];
+                let first_byte = (area.position.x / 8) as usize;
+                let shift = area.position.x % 8;
+
+                // Move the first pixel of the area to bit 0, like a texture region.
+                for (i, byte) in row.iter_mut().enumerate() {
+                    let next = line.get(first_byte + i + 1).map_or(0, |b| *b as u16) << 8;
+                    *byte = ((next | line[first_byte + i] as u16) >> shift) as u8;
+                }
+            } else {
+                for x in 0..area.width {
+                    if self.pixel(Vect2D::new(area.position.x + x, source_y)) == Some(WHITE) {
+                        row[(x / 8) as usize] |= SET[(x % 8) as usize];
+                    }
+                }
+            }
+        }
+
+        bitmap
+    }
+
+    fn fill_area(&mut self, area: &Rect, color: bool) -> anyhow::Result<()> {
+        for y in area.position.y..area.position.y + area.height {
+            self.draw_hline(Vect2D::new(area.position.x, y), area.width, color)?;
         }
 
         Ok(())
     }
 
-    fn draw_line(&mut self, mut c1: Vect2D, mut c2: Vect2D, color: bool) -> anyhow::Result<()> {
-        if c1.x >= self.width || c1.y >= self.height || c2.x >= self.width || c2.y >= self.height {
-            return Err(anyhow::anyhow!("Values out of bounds"));
+    /// Blanks the panel, to black when inverted.
+    pub fn clear_display(&mut self) -> anyhow::Result<()> {
+        let display = match self.display.as_deref_mut() {
+            Some(display) => display,
+            None => return Ok(()),
+        };
+
+        if self.inverted {
+            self.inverted_line.fill(0x00);
+
+            for row in 0..self.buffer.len() {
+                display.refresh_line(line_num(row)?, &self.inverted_line)?;
+            }
+
+            return Ok(());
         }
 
-        let steep = unsafe { abs((c2.y - c1.y).into()) > abs((c2.x - c1.x).into()) };
+        display.clear_display()
+    }
+
+    pub fn draw(&mut self) -> anyhow::Result<()> {
+        let display = match self.display.as_deref_mut() {
+            Some(display) => display,
+            None => return Ok(()),
+        };
 
-        if steep {
-            swap(&mut c1.x, &mut c1.y);
-            swap(&mut c2.x, &mut c2.y);
+        if self.inverted {
+            // Sent row by row through one buffer rather than inverting a copy of the frame.
+            for (row, line) in self.buffer.iter().enumerate() {
+                invert_line(line, &mut self.inverted_line);
+                display.refresh_line(line_num(row)?, &self.inverted_line)?;
+            }
+        } else {
+            display.refresh(&self.buffer)?;
         }
 
-        if c1.x > c2.x {
-            swap(&mut c1, &mut c2);
+        self.dirty.fill(false);
+
+        Ok(())
+    }
+
+    /// Copies a packed 1-bit bitmap (rows of `ceil(width / 8)` bytes, same bit order as the
+    /// framebuffer) to `corner`, which does not need to be byte aligned. Parts of the bitmap
+    /// that fall outside the screen are clipped.
+    pub fn draw_bitmap(
+        &mut self,
+        corner: Vect2D,
+        bitmap: &[u8],
+        width: u16,
+        height: u16,
+    ) -> anyhow::Result<()> {
+        let stride = ((width + 7) / 8) as usize;
+
+        self.draw_bitmap_rows(corner, bitmap, width, height, stride, RasterOp::Copy)
+    }
+
+    pub fn draw_texture_data(&mut self, corner: Vect2D, texture: &Texture) -> anyhow::Result<()> {
+        self.draw_bitmap_rows(
+            corner,
+            &texture.data,
+            texture.width(),
+            texture.height(),
+            texture.stride(),
+            RasterOp::Copy,
+        )
+    }
+
+    pub fn draw_cached_texture(
+        &mut self,
+        corner: Vect2D,
+        path: &str,
+        cache: &mut TextureCache,
+    ) -> anyhow::Result<()> {
+        let texture = cache.get(path)?;
+
+        self.draw_texture_data(corner, texture)
+    }
+
+    /// Draws the part of a texture file starting at `source` (in texture pixels) to `corner`.
+    /// Only the rows and bytes that end up on screen are read, so textures larger than the
+    /// screen, like maps, can be panned without loading them into memory. The CRC is only
+    /// checked when the whole texture fits on screen, which is then drawn if it matches.
+    pub fn draw_texture_region_from_flash(
+        &mut self,
+        corner: Vect2D,
+        path: &str,
+        source: Vect2D,
+    ) -> anyhow::Result<()> {
+        if corner.x >= self.width || corner.y >= self.height {
+            return Err(anyhow!("Corner coordinates are out of screen bounds"));
         }
 
-        let dx = c2.x - c1.x;
-        let dy = unsafe { abs((c2.y - c1.y).into()) } as u16;
-        let mut err: i32 = (dx / 2).into();
+        let file = BufReader::with_capacity(STREAM_BUFFER_LEN, File::open(path)?);
 
-        while c1.x < c2.x {
-            c1.x += 1;
+        self.draw_texture_region(corner, file, source)
+            .map_err(|err| anyhow!("Invalid texture {}: {}", path, err))
+    }
 
-            if steep {
-                self.set_pixel(Vect2D { x: c1.y, y: c1.x }, color)?;
-            } else {
-                self.set_pixel(Vect2D { x: c1.x, y: c1.y }, color)?;
+    pub fn draw_texture_region<R: Read + Seek>(
+        &mut self,
+        corner: Vect2D,
+        reader: R,
+        source: Vect2D,
+    ) -> anyhow::Result<()> {
+        let mut reader = TextureReader::new(reader)?;
+        let header = *reader.header();
+
+        if source.x >= header.width
+            || source.y >= header.height
+            || corner.x >= self.width
+            || corner.y >= self.height
+        {
+            return Ok(());
+        }
+
+        let width = (header.width - source.x).min(self.width - corner.x);
+        let height = (header.height - source.y).min(self.height - corner.y);
+
+        // The whole texture fits on screen, so it is read in order and only drawn once its
+        // CRC matched.
+        if source == Vect2D::new(0, 0) && width == header.width && height == header.height {
+            let stride = header.stride as usize;
+            let mut data = vec![0; header.data_len()];
+
+            for y in 0..height as usize {
+                reader.read_row(&mut data[y * stride..(y + 1) * stride])?;
+            }
+
+            reader.finish()?;
+
+            return self.draw_bitmap_rows(corner, &data, width, height, stride, RasterOp::Copy);
+        }
+
+        let shift = source.x % 8;
+        let mut span = vec![0; ((shift + width + 7) / 8) as usize];
+        let mut row = vec![0; ((width + 7) / 8) as usize];
+
+        for y in 0..height {
+            reader.read_row_span(source.y + y, (source.x / 8) as usize, &mut span)?;
+
+            // Move the first visible pixel of the span to bit 0.
+            for (i, byte) in row.iter_mut().enumerate() {
+                let next = span.get(i + 1).map_or(0, |b| *b as u16) << 8;
+                *byte = ((next | span[i] as u16) >> shift) as u8;
+            }
+
+            self.draw_bitmap_rows(
+                Vect2D::new(corner.x, corner.y + y),
+                &row,
+                width,
+                1,
+                row.len(),
+                RasterOp::Copy,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_bitmap_rows(
+        &mut self,
+        corner: Vect2D,
+        bitmap: &[u8],
+        width: u16,
+        height: u16,
+        stride: usize,
+        op: RasterOp,
+    ) -> anyhow::Result<()> {
+        if bitmap.len() < stride * height as usize || stride * 8 < width as usize {
+            return Err(anyhow!("Bitmap data is shorter than its dimensions"));
+        }
+
+        if corner.x >= self.width || corner.y >= self.height {
+            return Ok(());
+        }
+
+        if !self.can_copy_rows() {
+            for row in 0..height.min(self.height - corner.y) {
+                for column in 0..width.min(self.width - corner.x) {
+                    let byte = bitmap[row as usize * stride + (column / 8) as usize];
+                    let c = Vect2D::new(corner.x + column, corner.y + row);
+                    let source = byte & SET[(column % 8) as usize] != 0;
+
+                    if op == RasterOp::Copy {
+                        self.set_pixel(c, source)?;
+                    } else if let Some(destination) = self.pixel(c) {
+                        self.set_pixel(c, op.apply(source as u8, destination as u8) & 1 != 0)?;
+                    }
+                }
             }
 
-            err -= dy as i32;
+            return Ok(());
+        }
+
+        let shift = corner.x % 8;
+        let first_byte = (corner.x / 8) as usize;
+        let row_bytes = ((width + 7) / 8) as usize;
 
-            if err < 0 {
-                if c1.y < c2.y {
-                    c1.y += 1;
+        for row in 0..height.min(self.height - corner.y) {
+            let src = &bitmap[row as usize * stride..row as usize * stride + row_bytes];
+            let line = &mut self.buffer[(corner.y + row) as usize];
+            self.dirty[(corner.y + row) as usize] = true;
+
+            for (i, byte) in src.iter().enumerate() {
+                let remaining = width - i as u16 * 8;
+                let mask: u16 = if remaining >= 8 {
+                    0xFF << shift
                 } else {
-                    c1.y -= 1;
+                    ((1 << remaining) - 1) << shift
+                };
+                let bits = ((*byte as u16) << shift) & mask;
+
+                if let Some(dest) = line.get_mut(first_byte + i) {
+                    *dest = (*dest & !(mask as u8)) | (op.apply(bits as u8, *dest) & mask as u8);
+                }
+
+                if let Some(dest) = line.get_mut(first_byte + i + 1) {
+                    let mask = (mask >> 8) as u8;
+
+                    *dest = (*dest & !mask) | (op.apply((bits >> 8) as u8, *dest) & mask);
                 }
-                err += dx as i32;
             }
         }
 
         Ok(())
     }
 
-    fn draw_hline(&mut self, c: Vect2D, len: u16, color: bool) -> anyhow::Result<()> {
+    /// Horizontal line in framebuffer coordinates, already checked to be on screen.
+    fn physical_hline(&mut self, c: Vect2D, len: u16, color: bool) {
+        self.dirty[c.y as usize] = true;
+
         let left_overlap = c.x % 8;
         let right_overflow = (c.x + len) % 8;
 
@@ -135,7 +713,7 @@ impl Draw<bool> for MonoGraphics<'_> {
                     SHO[(7 - left_overlap) as usize] | CAP[(7 - left_overlap - len) as usize];
             }
 
-            return Ok(());
+            return;
         }
 
         if left_overlap != 0 {
@@ -161,14 +739,11 @@ impl Draw<bool> for MonoGraphics<'_> {
         for i in (c.x + 7) / 8..(c.x + (len - right_overflow)) / 8 {
             self.buffer[c.y as usize][i as usize] = color as u8 * 0xff;
         }
-
-        Ok(())
     }
 
-    fn draw_vline(&mut self, c: Vect2D, height: u16, color: bool) -> anyhow::Result<()> {
-        if c.x >= self.width || c.y >= self.height || c.y + height >= self.width {
-            return Err(anyhow!("Line dimensions out of bounds"));
-        }
+    /// Vertical line in framebuffer coordinates, already checked to be on screen.
+    fn physical_vline(&mut self, c: Vect2D, height: u16, color: bool) {
+        self.dirty[c.y as usize..(c.y + height) as usize].fill(true);
 
         let offset = c.x % 8;
         let coord = (c.x - offset) / 8;
@@ -180,6 +755,147 @@ impl Draw<bool> for MonoGraphics<'_> {
                 self.buffer[(c.y + i) as usize][coord as usize] &= CLR[offset as usize];
             }
         }
+    }
+}
+
+fn line_num(row: usize) -> anyhow::Result<u8> {
+    u8::try_from(row).map_err(|_| anyhow!("Row {} can not be sent", row))
+}
+
+fn invert_line(line: &[u8], inverted: &mut [u8]) {
+    for (inverted, byte) in inverted.iter_mut().zip(line) {
+        *inverted = !byte;
+    }
+}
+
+/// Shifts the pixels of a run of framebuffer bytes `distance` pixels to the right, or left
+/// when negative. Pixels shifted past either end are dropped and zeros are shifted in.
+fn shift_bits(bytes: &mut [u8], distance: i16) {
+    let len = bytes.len();
+    let whole = (distance.unsigned_abs() / 8) as usize;
+    let bits = distance.unsigned_abs() % 8;
+
+    if whole >= len {
+        bytes.fill(0);
+        return;
+    }
+
+    if distance > 0 {
+        bytes.copy_within(..len - whole, whole);
+        bytes[..whole].fill(0);
+
+        if bits != 0 {
+            for i in (0..len).rev() {
+                let carry = if i > 0 { bytes[i - 1] >> (8 - bits) } else { 0 };
+                bytes[i] = (bytes[i] << bits) | carry;
+            }
+        }
+    } else {
+        bytes.copy_within(whole.., 0);
+        bytes[len - whole..].fill(0);
+
+        if bits != 0 {
+            for i in 0..len {
+                let carry = bytes.get(i + 1).map_or(0, |next| next << (8 - bits));
+                bytes[i] = (bytes[i] >> bits) | carry;
+            }
+        }
+    }
+}
+
+impl SetPixel<bool> for MonoGraphics<'_> {
+    fn set_pixel(&mut self, c: Vect2D, color: bool) -> anyhow::Result<()> {
+        if c.x >= self.width || c.y >= self.height {
+            return Err(anyhow!("Pixel out of bounds"));
+        }
+
+        if !self.clip.contains(c) {
+            return Ok(());
+        }
+
+        let c = self.to_physical(c);
+        let left: u8 = (c.x % 8) as u8;
+        self.dirty[c.y as usize] = true;
+        let whole: u16 = (c.x - left as u16) / 8;
+
+        if color {
+            self.buffer[c.y as usize][whole as usize] |= SET[left as usize];
+        } else {
+            self.buffer[c.y as usize][whole as usize] &= CLR[left as usize];
+        }
+
+        Ok(())
+    }
+}
+
+impl Draw<bool> for MonoGraphics<'_> {
+    /// Fills the screen, or only the clip if there is one.
+    fn clear(&mut self, color: bool) -> anyhow::Result<()> {
+        if self.clip != self.screen() {
+            let clip = self.clip;
+
+            return self.fill_area(&clip, color);
+        }
+
+        let line_color = if color { 0xFF } else { 0x00 };
+
+        for i in 0..self.buffer.len() {
+            self.buffer[i].fill(line_color);
+        }
+
+        self.mark_dirty();
+
+        Ok(())
+    }
+
+    fn draw_line(&mut self, c1: Vect2D, c2: Vect2D, color: bool) -> anyhow::Result<()> {
+        if c1.x >= self.width || c1.y >= self.height || c2.x >= self.width || c2.y >= self.height {
+            return Err(anyhow::anyhow!("Values out of bounds"));
+        }
+
+        plot_line(c1, c2, |c| self.set_pixel(c, color))
+    }
+
+    fn draw_hline(&mut self, c: Vect2D, len: u16, color: bool) -> anyhow::Result<()> {
+        if c.y >= self.height || c.x as u32 + len as u32 > self.width as u32 {
+            return Err(anyhow!("Line dimensions out of bounds"));
+        }
+
+        let (c, len) = match Rect::new(c, len, 1).intersection(&self.clip) {
+            Some(line) => (line.position, line.width),
+            None => return Ok(()),
+        };
+
+        let start = self.to_physical(c);
+        let end = self.to_physical(Vect2D::new(c.x + len - 1, c.y));
+
+        if start.y == end.y {
+            self.physical_hline(Vect2D::new(start.x.min(end.x), start.y), len, color);
+        } else {
+            self.physical_vline(Vect2D::new(start.x, start.y.min(end.y)), len, color);
+        }
+
+        Ok(())
+    }
+
+    fn draw_vline(&mut self, c: Vect2D, height: u16, color: bool) -> anyhow::Result<()> {
+        if c.x >= self.width || c.y as u32 + height as u32 > self.height as u32 {
+            return Err(anyhow!("Line dimensions out of bounds"));
+        }
+
+        let (c, height) = match Rect::new(c, 1, height).intersection(&self.clip) {
+            Some(line) => (line.position, line.height),
+            None => return Ok(()),
+        };
+
+        let start = self.to_physical(c);
+        let end = self.to_physical(Vect2D::new(c.x, c.y + height - 1));
+
+        if start.x == end.x {
+            self.physical_vline(Vect2D::new(start.x, start.y.min(end.y)), height, color);
+        } else {
+            self.physical_hline(Vect2D::new(start.x.min(end.x), start.y), height, color);
+        }
 
         Ok(())
     }
@@ -190,37 +906,15 @@ impl Draw<bool> for MonoGraphics<'_> {
         corner2: Vect2D,
         color: bool,
     ) -> anyhow::Result<()> {
-        unsafe {
-            self.draw_hline(
-                corner1,
-                abs(corner1.x as i32 - corner2.x as i32 - 1).try_into()?,
-                color,
-            )?;
-
-            self.draw_hline(
-                Vect2D {
-                    x: corner1.x,
-                    y: corner2.y,
-                },
-                abs(corner1.x as i32 - corner2.x as i32 - 1).try_into()?,
-                color,
-            )?;
-
-            self.draw_vline(
-                corner1,
-                abs(corner1.y as i32 - corner2.y as i32 - 1).try_into()?,
-                color,
-            )?;
+        let (left, right) = (corner1.x.min(corner2.x), corner1.x.max(corner2.x));
+        let (top, bottom) = (corner1.y.min(corner2.y), corner1.y.max(corner2.y));
+        let width = (right - left).saturating_add(1);
+        let height = (bottom - top).saturating_add(1);
 
-            self.draw_vline(
-                Vect2D {
-                    x: corner2.x,
-                    y: corner1.y,
-                },
-                abs(corner1.y as i32 - corner2.y as i32 - 1).try_into()?,
-                color,
-            )?;
-        }
+        self.draw_hline(Vect2D::new(left, top), width, color)?;
+        self.draw_hline(Vect2D::new(left, bottom), width, color)?;
+        self.draw_vline(Vect2D::new(left, top), height, color)?;
+        self.draw_vline(Vect2D::new(right, top), height, color)?;
 
         Ok(())
     }
@@ -231,31 +925,46 @@ impl Draw<bool> for MonoGraphics<'_> {
         corner2: Vect2D,
         color: bool,
     ) -> anyhow::Result<()> {
-        unsafe {
-            for i in 0..abs(corner1.y as i32 - corner2.y as i32).try_into()? {
-                self.draw_hline(
-                    Vect2D {
-                        x: corner1.x,
-                        y: corner1.y + i,
-                    },
-                    abs(corner1.x as i32 - corner2.x as i32).try_into()?,
-                    color,
-                )?;
-            }
+        let left = corner1.x.min(corner2.x);
+        let top = corner1.y.min(corner2.y);
+
+        for i in 0..corner1.y.abs_diff(corner2.y) {
+            self.draw_hline(
+                Vect2D {
+                    x: left,
+                    y: top + i,
+                },
+                corner1.x.abs_diff(corner2.x),
+                color,
+            )?;
         }
 
         Ok(())
     }
 
     fn draw_texture(&mut self, corner: Vect2D, texture: &Vec<Vec<u8>>) -> anyhow::Result<()> {
-        if texture.len() == 0 {
+        if corner.x >= self.width {
             return Ok(());
         }
 
-        for i in corner.y..texture.len().clamp(0, self.height as usize) as u16 {
-            for j in corner.x..texture[0].len().clamp(0, self.width as usize) as u16 {
-                self.buffer[i as usize][j as usize] = texture[i as usize][j as usize];
+        for (i, row) in texture.iter().enumerate() {
+            let y = corner.y as usize + i;
+
+            if y >= self.height as usize {
+                break;
             }
+
+            // Whole bytes are drawn, but only the ones reaching the screen.
+            let width = (row.len() * 8).min((self.width - corner.x) as usize) as u16;
+
+            self.draw_bitmap_rows(
+                Vect2D::new(corner.x, y as u16),
+                row,
+                width,
+                1,
+                row.len(),
+                RasterOp::Copy,
+            )?;
         }
 
         Ok(())
@@ -266,58 +975,124 @@ impl Draw<bool> for MonoGraphics<'_> {
             return Err(anyhow!("Corner coordinates are out of screen bounds"));
         }
 
-        let texture = std::fs::read(path)?;
-
-        let w = texture[0] as u16 | ((texture[1] as u16) << 8);
-        let h = texture[2] as u16 | ((texture[3] as u16) << 8);
-        let acutal_x = corner.x + 8 - (corner.x % 8);
-        let total_h = corner.y + h;
-        let total_w = acutal_x + w;
+        self.draw_texture_region_from_flash(corner, path, Vect2D::new(0, 0))
+    }
 
-        for i in corner.y..total_h - (total_h % self.height) {
-            for j in acutal_x / 8..(total_w - (total_w % self.width)) / 8 {
-                self.buffer[i as usize][j as usize] =
-                    texture[(i * self.width / 8 + j + 4) as usize];
-            }
+    fn draw_image_from_flash(&mut self, corner: Vect2D, path: &str) -> anyhow::Result<()> {
+        if corner.x >= self.width || corner.y >= self.height {
+            return Err(anyhow!("Corner coordinates are out of screen bounds"));
         }
 
-        Ok(())
+        let image = load_image(path)?;
+
+        self.draw_texture_data(corner, &image)
     }
 }
 
 impl Print<bool> for MonoGraphics<'_> {
-    fn put_char(&mut self, c: &Vect2D, chr: char, color: bool) -> anyhow::Result<()> {
+    fn put_char(
+        &mut self,
+        c: &Vect2D,
+        chr: char,
+        color: bool,
+        background: Option<bool>,
+    ) -> anyhow::Result<()> {
         if c.x >= self.width || c.y >= self.height {
             return Ok(());
         }
 
-        for i in 0..5 {
-            let mut line: u8 = GLCD_FONT[(chr as usize) * 5 + i];
+        let columns = glcd_columns(chr).unwrap_or(GLCD_REPLACEMENT);
+        let width = 5.min(self.width - c.x);
+        let height = 8.min(self.height - c.y);
+
+        for (i, &column) in columns.iter().enumerate().take(width as usize) {
+            let mut line: u8 = column;
 
-            for j in 0..8 {
-                if (line & 1) == 1 {
+            for j in 0..height {
+                let pixel = if (line & 1) == 1 {
+                    Some(color)
+                } else {
+                    background
+                };
+
+                if let Some(pixel) = pixel {
                     self.set_pixel(
                         Vect2D {
                             x: c.x + i as u16,
                             y: c.y + j,
                         },
-                        color,
+                        pixel,
                     )?;
+                }
+
+                line >>= 1;
+            }
+        }
+
+        if let Some(background) = background {
+            if c.x + 5 < self.width {
+                self.draw_vline(Vect2D { x: c.x + 5, y: c.y }, height, background)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn put_glyph(
+        &mut self,
+        c: &Vect2D,
+        glyph: &Glyph,
+        color: bool,
+        background: Option<bool>,
+    ) -> anyhow::Result<()> {
+        self.put_glyph_transformed(c, glyph, GlyphTransform::default(), color, background)
+    }
+
+    fn put_glyph_transformed(
+        &mut self,
+        c: &Vect2D,
+        glyph: &Glyph,
+        transform: GlyphTransform,
+        color: bool,
+        background: Option<bool>,
+    ) -> anyhow::Result<()> {
+        let (width, height) = transform.size(&glyph.metrics);
+        let width = width.min(self.width.saturating_sub(c.x));
+        let height = height.min(self.height.saturating_sub(c.y));
+
+        for y in 0..height {
+            for x in 0..width {
+                let (u, v) = transform.source(&glyph.metrics, x, y);
+                let pixel = if glyph.is_set(u, v) {
+                    Some(color)
                 } else {
+                    background
+                };
+
+                if let Some(pixel) = pixel {
                     self.set_pixel(
                         Vect2D {
-                            x: c.x + i as u16,
-                            y: c.y + j,
+                            x: c.x + x,
+                            y: c.y + y,
                         },
-                        !color,
+                        pixel,
                     )?;
                 }
-
-                line >>= 1;
             }
         }
 
-        self.draw_vline(Vect2D { x: c.x + 5, y: c.y }, 8, !color)?;
+        Ok(())
+    }
+
+    fn fill_background(&mut self, area: &Rect, color: bool) -> anyhow::Result<()> {
+        let right = (area.position.x as u32 + area.width as u32).min(self.width as u32) as u16;
+        let bottom = (area.position.y as u32 + area.height as u32).min(self.height as u32) as u16;
+
+        for y in area.position.y..bottom {
+            for x in area.position.x..right {
+                self.set_pixel(Vect2D { x, y }, color)?;
+            }
+        }
 
         Ok(())
     }
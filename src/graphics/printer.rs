@@ -1,33 +1,367 @@
-use super::{Print, Vect2D};
+use super::{
+    layout_text, measure_text, text_width, Font, GlcdFont, GlyphTransform, HorizontalAlign,
+    Overflow, Print, Rect, Rotation, TextLayout, Vect2D, VerticalAnchor,
+};
 
-pub struct Printer<T> {
+/// Text is laid out in its own space, `u` along the baseline and `v` down across the lines,
+/// and rotated onto the screen around a line's starting point.
+pub struct Printer<'f, T> {
     cursor_position: Vect2D,
+    /// Start of the current line, the cursor returns below it after a newline.
+    line_start: Vect2D,
+    line_spacing: i16,
+    align: HorizontalAlign,
+    anchor: VerticalAnchor,
+    transform: GlyphTransform,
     color: T,
+    background: Option<T>,
+    font: &'f dyn Font,
 }
 
-impl<T: Clone> Printer<T> {
+impl<'f, T: Clone> Printer<'f, T> {
+    pub fn new(cursor_position: Vect2D, color: T) -> Self {
+        Printer {
+            cursor_position,
+            line_start: cursor_position,
+            line_spacing: 0,
+            align: HorizontalAlign::Left,
+            anchor: VerticalAnchor::Top,
+            transform: GlyphTransform::default(),
+            color,
+            background: None,
+            font: &GlcdFont,
+        }
+    }
+
     pub fn set_position(&mut self, cursor_position: Vect2D) {
         self.cursor_position = cursor_position;
+        self.line_start = cursor_position;
     }
 
     pub fn set_color(&mut self, color: T) {
         self.color = color;
     }
 
+    /// Color painted behind the text, covering each line from its top to the bottom of the
+    /// font's line height. Without one, text is drawn over what is already on screen.
+    pub fn set_background(&mut self, background: Option<T>) {
+        self.background = background;
+    }
+
+    pub fn set_font(&mut self, font: &'f dyn Font) {
+        self.font = font;
+    }
+
+    /// Pixels added to the font's line height between lines, can be negative.
+    pub fn set_line_spacing(&mut self, line_spacing: i16) {
+        self.line_spacing = line_spacing;
+    }
+
+    /// How lines are aligned to the cursor, or within the box of [`Printer::print_in`].
+    pub fn set_align(&mut self, align: HorizontalAlign) {
+        self.align = align;
+    }
+
+    /// Which part of the printed text is placed at the cursor, or where it sits vertically in
+    /// the box of [`Printer::print_in`].
+    pub fn set_anchor(&mut self, anchor: VerticalAnchor) {
+        self.anchor = anchor;
+    }
+
+    /// Draws every font pixel as a `scale` x `scale` block, 0 is treated as 1.
+    pub fn set_scale(&mut self, scale: u16) {
+        self.transform.scale = scale.max(1);
+    }
+
+    /// Rotates text clockwise around the cursor. `Deg90` reads top to bottom, `Deg270` bottom
+    /// to top.
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.transform.rotation = rotation;
+    }
+
+    /// Draws `text` at the cursor, aligned and anchored to it, and moves the cursor past it.
+    /// A newline moves the cursor to the start of the next line.
+    /// Characters missing from the font are drawn with its replacement glyph.
     pub fn print<U>(&mut self, printable_interface: &mut U, text: &str) -> anyhow::Result<()>
     where
         U: Print<T>,
     {
-        for chr in text.as_bytes().into_iter() {
-            printable_interface.put_char(
-                &self.cursor_position,
-                *chr as char,
-                self.color.clone(),
+        let lines: Vec<&str> = text.split('\n').collect();
+        let origin = point(self.line_start);
+        let (x, y) = point(self.cursor_position);
+        let (dx, dy) = (x - origin.0, y - origin.1);
+        let first_u = match self.transform.rotation {
+            Rotation::Deg0 => dx,
+            Rotation::Deg90 => dy,
+            Rotation::Deg180 => -dx,
+            Rotation::Deg270 => -dy,
+        };
+        let top = -self.anchor_offset(self.block_height(lines.len()));
+
+        self.print_lines(printable_interface, &lines, origin, first_u, top)
+    }
+
+    /// Draws `text` wrapped to fit inside `bounds`, aligned and anchored within it. Lines that
+    /// do not fit are cut according to `overflow`, the cursor is left at the end of the last
+    /// drawn line. [`VerticalAnchor::Baseline`] puts the last baseline on the bottom edge.
+    pub fn print_in<U>(
+        &mut self,
+        printable_interface: &mut U,
+        text: &str,
+        bounds: Rect,
+        overflow: Overflow,
+    ) -> anyhow::Result<()>
+    where
+        U: Print<T>,
+    {
+        let (width, height) = self.text_size(bounds.width, bounds.height);
+        let layout = TextLayout {
+            overflow,
+            ..self.layout(Some(width), Some(height))
+        };
+        let laid_out = layout_text(self.font, text, &layout);
+        let lines: Vec<&str> = laid_out.iter().map(|line| line.text.as_ref()).collect();
+        let free = height as i32 - self.block_height(lines.len());
+        let top = match self.anchor {
+            VerticalAnchor::Top => 0,
+            VerticalAnchor::Middle => free / 2,
+            VerticalAnchor::Baseline => free + self.scaled(self.font.descent() as i32),
+            VerticalAnchor::Bottom => free,
+        };
+        let anchor_u = match self.align {
+            HorizontalAlign::Left => 0,
+            HorizontalAlign::Center => width as i32 / 2,
+            HorizontalAlign::Right => width as i32,
+        };
+
+        // The corner of the box where text space starts, seen from the rotated text.
+        let (x, y) = point(bounds.position);
+        let corner = match self.transform.rotation {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (x + bounds.width as i32, y),
+            Rotation::Deg180 => (x + bounds.width as i32, y + bounds.height as i32),
+            Rotation::Deg270 => (x, y + bounds.height as i32),
+        };
+        let origin = self.to_screen(corner, anchor_u, 0);
+
+        self.print_lines(printable_interface, &lines, origin, 0, top)
+    }
+
+    /// The box `text` would cover if printed at the cursor, taking the alignment, anchor,
+    /// scale and rotation into account.
+    pub fn measure(&self, text: &str) -> Rect {
+        let size = measure_text(self.font, text, &self.layout(None, None));
+        let (x, y, width, height) = self.screen_box(
+            point(self.cursor_position),
+            self.align.offset(size.width),
+            -self.anchor_offset(size.height as i32),
+            size.width as i32,
+            size.height as i32,
+        );
+
+        Rect::new(Vect2D::new(clamp(x), clamp(y)), clamp(width), clamp(height))
+    }
+
+    /// The box `text` would cover if printed with [`Printer::print_in`] into a box that is
+    /// `max_width` long in the direction of the text, placed at (0, 0).
+    pub fn measure_in(&self, text: &str, max_width: u16) -> Rect {
+        let size = measure_text(self.font, text, &self.layout(Some(max_width), None));
+        let (width, height) = self.text_size(size.width, size.height);
+
+        Rect::new(size.position, width, height)
+    }
+
+    fn layout(&self, max_width: Option<u16>, max_height: Option<u16>) -> TextLayout {
+        TextLayout {
+            max_width,
+            max_height,
+            line_spacing: self.line_spacing,
+            scale: self.transform.scale,
+            ..Default::default()
+        }
+    }
+
+    /// Swaps width and height between the screen and text space when the text is on its
+    /// side.
+    fn text_size(&self, width: u16, height: u16) -> (u16, u16) {
+        match self.transform.rotation {
+            Rotation::Deg0 | Rotation::Deg180 => (width, height),
+            Rotation::Deg90 | Rotation::Deg270 => (height, width),
+        }
+    }
+
+    fn scaled(&self, value: i32) -> i32 {
+        value * self.transform.scale as i32
+    }
+
+    /// Distance from the top of a block of text `height` pixels high to its anchor point.
+    fn anchor_offset(&self, height: i32) -> i32 {
+        match self.anchor {
+            VerticalAnchor::Top => 0,
+            VerticalAnchor::Middle => height / 2,
+            VerticalAnchor::Baseline => self.scaled(self.font.ascent() as i32),
+            VerticalAnchor::Bottom => height,
+        }
+    }
+
+    fn block_height(&self, lines: usize) -> i32 {
+        let layout = self.layout(None, None);
+
+        layout.line_height(self.font) + (lines as i32 - 1).max(0) * layout.line_advance(self.font)
+    }
+
+    /// Screen position of the text space point (`u`, `v`) relative to `origin`.
+    fn to_screen(&self, origin: (i32, i32), u: i32, v: i32) -> (i32, i32) {
+        let (x, y) = origin;
+
+        match self.transform.rotation {
+            Rotation::Deg0 => (x + u, y + v),
+            Rotation::Deg90 => (x - v, y + u),
+            Rotation::Deg180 => (x - u, y - v),
+            Rotation::Deg270 => (x + v, y - u),
+        }
+    }
+
+    /// Screen area, as left, top, width and height, of a `width` x `height` text space box
+    /// at (`u`, `v`).
+    fn screen_box(
+        &self,
+        origin: (i32, i32),
+        u: i32,
+        v: i32,
+        width: i32,
+        height: i32,
+    ) -> (i32, i32, i32, i32) {
+        let (x1, y1) = self.to_screen(origin, u, v);
+        let (x2, y2) = self.to_screen(origin, u + width, v + height);
+
+        (x1.min(x2), y1.min(y2), (x2 - x1).abs(), (y2 - y1).abs())
+    }
+
+    /// Draws `lines` with the top of the first one at `top`. The first line starts `first_u`
+    /// along the baseline from `origin`, the following ones at `origin`.
+    fn print_lines<U>(
+        &mut self,
+        printable_interface: &mut U,
+        lines: &[&str],
+        origin: (i32, i32),
+        first_u: i32,
+        top: i32,
+    ) -> anyhow::Result<()>
+    where
+        U: Print<T>,
+    {
+        let advance = self.layout(None, None).line_advance(self.font);
+
+        for (i, line) in lines.iter().enumerate() {
+            let start = if i == 0 { first_u } else { 0 };
+            let width = self.scaled(text_width(self.font, line) as i32);
+            let u = start + self.align.offset(width.min(u16::MAX as i32) as u16);
+
+            self.print_line(
+                printable_interface,
+                line,
+                origin,
+                u,
+                top + i as i32 * advance,
             )?;
+        }
 
-            self.cursor_position.x += 6;
+        Ok(())
+    }
+
+    fn print_line<U>(
+        &mut self,
+        printable_interface: &mut U,
+        text: &str,
+        origin: (i32, i32),
+        left: i32,
+        top: i32,
+    ) -> anyhow::Result<()>
+    where
+        U: Print<T>,
+    {
+        let baseline = top + self.scaled(self.font.ascent() as i32);
+        let mut pen = left;
+        let mut previous: Option<char> = None;
+
+        if let Some(background) = self.background.clone() {
+            let (x, y, width, height) = self.screen_box(
+                origin,
+                left,
+                top,
+                self.scaled(text_width(self.font, text) as i32),
+                self.scaled(self.font.line_height() as i32),
+            );
+
+            if x + width > 0 && y + height > 0 && x <= u16::MAX as i32 && y <= u16::MAX as i32 {
+                let (left, top) = (x.max(0), y.max(0));
+
+                printable_interface.fill_background(
+                    &Rect::new(
+                        Vect2D::new(left as u16, top as u16),
+                        clamp(x + width - left),
+                        clamp(y + height - top),
+                    ),
+                    background,
+                )?;
+            }
+        }
+
+        for chr in text.chars() {
+            if chr.is_control() {
+                continue;
+            }
+
+            let glyph = match self
+                .font
+                .glyph(chr)
+                .or_else(|| self.font.replacement_glyph())
+            {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+
+            if let Some(previous) = previous {
+                pen += self.scaled(self.font.kerning(previous, chr) as i32);
+            }
+
+            let (x, y, _, _) = self.screen_box(
+                origin,
+                pen + self.scaled(glyph.metrics.x_offset as i32),
+                baseline - self.scaled(glyph.metrics.y_offset as i32),
+                self.scaled(glyph.metrics.width as i32),
+                self.scaled(glyph.metrics.height as i32),
+            );
+
+            if x >= 0 && y >= 0 && x <= u16::MAX as i32 && y <= u16::MAX as i32 {
+                printable_interface.put_glyph_transformed(
+                    &Vect2D::new(x as u16, y as u16),
+                    &glyph,
+                    self.transform,
+                    self.color.clone(),
+                    None,
+                )?;
+            }
+
+            pen += self.scaled(glyph.metrics.advance as i32);
+            previous = Some(chr);
         }
 
+        let (x, y) = self.to_screen(origin, 0, top);
+        self.line_start = Vect2D::new(clamp(x), clamp(y));
+
+        let (x, y) = self.to_screen(origin, pen, top);
+        self.cursor_position = Vect2D::new(clamp(x), clamp(y));
+
         Ok(())
     }
 }
+
+fn point(position: Vect2D) -> (i32, i32) {
+    (position.x as i32, position.y as i32)
+}
+
+fn clamp(value: i32) -> u16 {
+    value.clamp(0, u16::MAX as i32) as u16
+}
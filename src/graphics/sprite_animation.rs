@@ -0,0 +1,129 @@
+use crate::filesystem::{read_sprite_sheet, SpriteSheet};
+
+use super::{MonoGraphics, Vect2D};
+
+pub struct SpriteAnimation {
+    sheet: SpriteSheet,
+    current_frame: u16,
+    elapsed_ms: u32,
+    looping: bool,
+}
+
+impl SpriteAnimation {
+    pub fn new(sheet: SpriteSheet, looping: bool) -> Self {
+        SpriteAnimation {
+            sheet,
+            current_frame: 0,
+            elapsed_ms: 0,
+            looping,
+        }
+    }
+
+    pub fn from_flash(path: &str, looping: bool) -> anyhow::Result<Self> {
+        Ok(Self::new(read_sprite_sheet(path)?, looping))
+    }
+
+    pub fn current_frame(&self) -> u16 {
+        self.current_frame
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.current_frame + 1 == self.sheet.frame_count
+    }
+
+    pub fn reset(&mut self) {
+        self.current_frame = 0;
+        self.elapsed_ms = 0;
+    }
+
+    /// Advances the animation by `delta_ms` and returns true if the visible frame changed.
+    pub fn tick(&mut self, delta_ms: u32) -> bool {
+        if self.sheet.frame_count == 0 {
+            return false;
+        }
+
+        let previous_frame = self.current_frame;
+        let frame_duration = (self.sheet.frame_duration_ms as u64).max(1);
+        let elapsed_ms = self.elapsed_ms as u64 + delta_ms as u64;
+        let frames = elapsed_ms / frame_duration;
+        let next_frame = self.current_frame as u64 + frames;
+
+        self.elapsed_ms = (elapsed_ms % frame_duration) as u32;
+        self.current_frame = if self.looping {
+            (next_frame % self.sheet.frame_count as u64) as u16
+        } else {
+            next_frame.min(self.sheet.frame_count as u64 - 1) as u16
+        };
+
+        if self.is_finished() {
+            self.elapsed_ms = 0;
+        }
+
+        previous_frame != self.current_frame
+    }
+
+    pub fn draw(&self, graphics: &mut MonoGraphics, corner: Vect2D) -> anyhow::Result<()> {
+        match self.sheet.frame(self.current_frame) {
+            Some(frame) => graphics.draw_bitmap(
+                corner,
+                frame,
+                self.sheet.frame_width,
+                self.sheet.frame_height,
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animation(frame_count: u16, frame_duration_ms: u16, looping: bool) -> SpriteAnimation {
+        let sheet = SpriteSheet {
+            frame_width: 8,
+            frame_height: 1,
+            frame_count,
+            frame_duration_ms,
+            frames: vec![0; frame_count as usize],
+        };
+
+        SpriteAnimation::new(sheet, looping)
+    }
+
+    #[test]
+    fn advances_by_whole_frames() {
+        let mut animation = animation(4, 10, true);
+
+        assert!(!animation.tick(9));
+        assert!(animation.tick(1));
+        assert_eq!(animation.current_frame(), 1);
+        assert!(animation.tick(25));
+        assert_eq!(animation.current_frame(), 3);
+        assert!(animation.tick(5));
+        assert_eq!(animation.current_frame(), 0);
+    }
+
+    #[test]
+    fn skips_frames_after_a_long_delay() {
+        let mut animation = animation(3, 1, true);
+
+        animation.tick(u32::MAX);
+        assert_eq!(animation.current_frame(), (u32::MAX % 3) as u16);
+        animation.tick(u32::MAX);
+        assert_eq!(
+            animation.current_frame(),
+            ((u32::MAX as u64 * 2) % 3) as u16
+        );
+    }
+
+    #[test]
+    fn stops_on_the_last_frame_unless_looping() {
+        let mut animation = animation(3, 1, false);
+
+        assert!(animation.tick(u32::MAX));
+        assert_eq!(animation.current_frame(), 2);
+        assert!(animation.is_finished());
+        assert!(!animation.tick(1));
+    }
+}
@@ -0,0 +1,447 @@
+use std::borrow::Cow;
+
+use super::{Font, Rect, Vect2D};
+
+/// What happens to text that does not fit in the layout's box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Overflow {
+    Clip,
+    /// The last visible line ends with `…`, or `...` if the font has no such glyph.
+    Ellipsis,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HorizontalAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl HorizontalAlign {
+    /// Horizontal offset of a line `width` pixels wide from the point it is aligned to.
+    pub fn offset(self, width: u16) -> i32 {
+        match self {
+            HorizontalAlign::Left => 0,
+            HorizontalAlign::Center => -(width as i32 / 2),
+            HorizontalAlign::Right => -(width as i32),
+        }
+    }
+}
+
+/// Which part of a block of text is placed at the anchor point.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum VerticalAnchor {
+    #[default]
+    Top,
+    Middle,
+    /// The baseline of the first line.
+    Baseline,
+    Bottom,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextLayout {
+    pub max_width: Option<u16>,
+    pub max_height: Option<u16>,
+    /// Break lines at spaces to fit `max_width`, or inside words that are wider on their
+    /// own. Without wrapping, long lines are cut according to `overflow`.
+    pub wrap: bool,
+    /// Pixels added to the font's line height between lines, can be negative.
+    pub line_spacing: i16,
+    pub overflow: Overflow,
+    /// Integer magnification, every pixel of a glyph becomes a `scale` x `scale` block.
+    pub scale: u16,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        TextLayout {
+            max_width: None,
+            max_height: None,
+            wrap: true,
+            line_spacing: 0,
+            overflow: Overflow::Ellipsis,
+            scale: 1,
+        }
+    }
+}
+
+impl TextLayout {
+    /// Wraps text inside `width` x `height` pixels.
+    pub fn boxed(width: u16, height: u16) -> Self {
+        TextLayout {
+            max_width: Some(width),
+            max_height: Some(height),
+            ..Default::default()
+        }
+    }
+
+    pub fn line_height(&self, font: &dyn Font) -> i32 {
+        font.line_height() as i32 * self.scale.max(1) as i32
+    }
+
+    /// Distance between the tops of two consecutive lines.
+    pub fn line_advance(&self, font: &dyn Font) -> i32 {
+        (self.line_height(font) + self.line_spacing as i32).max(0)
+    }
+}
+
+pub struct TextLine<'t> {
+    pub text: Cow<'t, str>,
+    pub width: u16,
+}
+
+/// Width of a single line of text as the `Printer` draws it, with kerning and replacement
+/// glyphs. Control characters take no space.
+pub fn text_width(font: &dyn Font, text: &str) -> u16 {
+    pen_positions(font, text).last().map_or(0, |(_, pen)| pen)
+}
+
+/// For every character, the byte index just past it and the width of the text up to there.
+fn pen_positions<'a>(font: &'a dyn Font, text: &'a str) -> impl Iterator<Item = (usize, u16)> + 'a {
+    let mut pen: i32 = 0;
+    let mut previous: Option<char> = None;
+
+    text.char_indices().map(move |(index, chr)| {
+        if !chr.is_control() {
+            if let Some(glyph) = font.glyph(chr).or_else(|| font.replacement_glyph()) {
+                if let Some(previous) = previous {
+                    pen += font.kerning(previous, chr) as i32;
+                }
+
+                pen = (pen + glyph.metrics.advance as i32).max(0);
+                previous = Some(chr);
+            }
+        }
+
+        (index + chr.len_utf8(), pen.min(u16::MAX as i32) as u16)
+    })
+}
+
+/// Splits `text` into the lines that fit the layout, breaking at `\n` and wrapping or
+/// truncating lines wider than `max_width`.
+pub fn layout_text<'t>(font: &dyn Font, text: &'t str, layout: &TextLayout) -> Vec<TextLine<'t>> {
+    // Lay out in unscaled font pixels, widths scale exactly with the font.
+    let scale = layout.scale.max(1);
+    let max_width = layout.max_width.map(|max_width| max_width / scale);
+    let mut lines: Vec<&'t str> = Vec::new();
+
+    for paragraph in text.split('\n') {
+        match max_width {
+            Some(max_width) if layout.wrap => {
+                wrap_paragraph(font, paragraph, max_width, &mut lines)
+            }
+            _ => lines.push(paragraph),
+        }
+    }
+
+    let max_lines = match layout.max_height {
+        Some(max_height) => {
+            let advance = layout.line_advance(font);
+            let height = layout.line_height(font);
+
+            if (max_height as i32) < height {
+                0
+            } else if advance == 0 {
+                lines.len()
+            } else {
+                ((max_height as i32 - height) / advance + 1) as usize
+            }
+        }
+        None => lines.len(),
+    };
+    let truncated = lines.len() > max_lines;
+
+    lines.truncate(max_lines);
+
+    let last = lines.len().saturating_sub(1);
+
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let width = text_width(font, line);
+            // Wrapped lines only overflow when a single character is wider than the box,
+            // which is drawn anyway.
+            let fits = layout.wrap || max_width.map_or(true, |max_width| width <= max_width);
+
+            let line =
+                if layout.overflow == Overflow::Ellipsis && (!fits || (truncated && i == last)) {
+                    with_ellipsis(font, line, max_width)
+                } else if !fits {
+                    let line = &line[..fitting_len(font, line, max_width.unwrap_or(0))];
+
+                    TextLine {
+                        text: Cow::Borrowed(line),
+                        width: text_width(font, line),
+                    }
+                } else {
+                    TextLine {
+                        text: Cow::Borrowed(line),
+                        width,
+                    }
+                };
+
+            TextLine {
+                width: line.width.saturating_mul(scale),
+                ..line
+            }
+        })
+        .collect()
+}
+
+/// The box `text` covers when laid out with its top left corner at (0, 0).
+pub fn measure_text(font: &dyn Font, text: &str, layout: &TextLayout) -> Rect {
+    let lines = layout_text(font, text, layout);
+    let width = lines.iter().map(|line| line.width).max().unwrap_or(0);
+    let height = match lines.len() {
+        0 => 0,
+        count => layout.line_height(font) + (count as i32 - 1) * layout.line_advance(font),
+    };
+
+    Rect::new(
+        Vect2D::new(0, 0),
+        width,
+        height.clamp(0, u16::MAX as i32) as u16,
+    )
+}
+
+fn wrap_paragraph<'t>(
+    font: &dyn Font,
+    paragraph: &'t str,
+    max_width: u16,
+    lines: &mut Vec<&'t str>,
+) {
+    let mut rest = paragraph;
+
+    loop {
+        if text_width(font, rest) <= max_width {
+            lines.push(rest);
+            return;
+        }
+
+        let fitting = fitting_len(font, rest, max_width);
+
+        // Prefer the last space that keeps the line within the box, otherwise break the word.
+        let space = if rest[fitting..].starts_with(' ') {
+            Some(fitting)
+        } else {
+            rest[..fitting].rfind(' ')
+        };
+        let (end, next) = match space {
+            Some(space) if !rest[..space].trim_end().is_empty() => {
+                (rest[..space].trim_end().len(), space + 1)
+            }
+            _ => {
+                // Always take at least one character so narrow boxes still make progress.
+                let end = if fitting == 0 {
+                    rest.chars().next().map_or(rest.len(), char::len_utf8)
+                } else {
+                    fitting
+                };
+
+                (end, end)
+            }
+        };
+
+        lines.push(&rest[..end]);
+        rest = rest[next..].trim_start_matches(' ');
+
+        if rest.is_empty() {
+            return;
+        }
+    }
+}
+
+/// Length in bytes of the longest prefix of `text` that is at most `max_width` wide.
+fn fitting_len(font: &dyn Font, text: &str, max_width: u16) -> usize {
+    pen_positions(font, text)
+        .take_while(|&(_, width)| width <= max_width)
+        .last()
+        .map_or(0, |(end, _)| end)
+}
+
+fn with_ellipsis<'t>(font: &dyn Font, line: &str, max_width: Option<u16>) -> TextLine<'t> {
+    let ellipsis = if font.glyph('…').is_some() {
+        "…"
+    } else {
+        "..."
+    };
+    let mut end = line.len();
+
+    loop {
+        let text = format!("{}{}", line[..end].trim_end(), ellipsis);
+        let width = text_width(font, &text);
+
+        if end == 0 || max_width.map_or(true, |max_width| width <= max_width) {
+            return TextLine {
+                text: Cow::Owned(text),
+                width,
+            };
+        }
+
+        end = line[..end]
+            .char_indices()
+            .last()
+            .map_or(0, |(index, _)| index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{BitmapFont, BitmapGlyph, GlyphMetrics};
+
+    /// A font whose glyphs are all one pixel wide and tall, so widths count characters.
+    fn font(chars: &str) -> BitmapFont<'static> {
+        let mut chars: Vec<char> = chars.chars().collect();
+        chars.sort();
+
+        let glyphs = chars
+            .iter()
+            .enumerate()
+            .map(|(i, &chr)| BitmapGlyph {
+                chr,
+                metrics: GlyphMetrics {
+                    width: 1,
+                    height: 1,
+                    x_offset: 0,
+                    y_offset: 1,
+                    advance: 1,
+                },
+                offset: i as u32,
+            })
+            .collect();
+
+        BitmapFont {
+            ascent: 1,
+            descent: 0,
+            glyphs: Cow::Owned(glyphs),
+            bitmaps: Cow::Owned(vec![1; chars.len()]),
+            kerning: Cow::Borrowed(&[]),
+        }
+    }
+
+    fn plain() -> BitmapFont<'static> {
+        font(" .abcdefg")
+    }
+
+    fn lines(font: &dyn Font, text: &str, layout: &TextLayout) -> Vec<String> {
+        layout_text(font, text, layout)
+            .into_iter()
+            .map(|line| line.text.into_owned())
+            .collect()
+    }
+
+    fn wrapped(max_width: u16) -> TextLayout {
+        TextLayout {
+            max_width: Some(max_width),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn breaks_at_newlines() {
+        assert_eq!(
+            lines(&plain(), "ab\n\ncd", &TextLayout::default()),
+            ["ab", "", "cd"]
+        );
+    }
+
+    #[test]
+    fn wraps_at_the_last_space_that_fits() {
+        assert_eq!(
+            lines(&plain(), "abc de fgab", &wrapped(5)),
+            ["abc", "de", "fgab"]
+        );
+        assert_eq!(lines(&plain(), "abc  de", &wrapped(3)), ["abc", "de"]);
+    }
+
+    #[test]
+    fn breaks_words_wider_than_the_box() {
+        assert_eq!(lines(&plain(), "abcdefg", &wrapped(3)), ["abc", "def", "g"]);
+        // Every line takes at least one character, however narrow the box.
+        assert_eq!(lines(&plain(), "ab", &wrapped(0)), ["a", "b"]);
+    }
+
+    #[test]
+    fn wraps_in_unscaled_pixels() {
+        let layout = TextLayout {
+            scale: 2,
+            ..wrapped(6)
+        };
+        let widths: Vec<u16> = layout_text(&plain(), "abcdef", &layout)
+            .iter()
+            .map(|line| line.width)
+            .collect();
+
+        assert_eq!(lines(&plain(), "abcdef", &layout), ["abc", "def"]);
+        assert_eq!(widths, [6, 6]);
+    }
+
+    #[test]
+    fn truncates_to_max_height() {
+        let clip = TextLayout {
+            max_height: Some(2),
+            overflow: Overflow::Clip,
+            ..Default::default()
+        };
+        let spaced = TextLayout {
+            line_spacing: 1,
+            ..clip
+        };
+
+        assert_eq!(lines(&plain(), "a\nb\nc", &clip), ["a", "b"]);
+        assert_eq!(lines(&plain(), "a\nb\nc", &spaced), ["a"]);
+        assert!(lines(
+            &plain(),
+            "a",
+            &TextLayout {
+                max_height: Some(0),
+                ..clip
+            }
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn ends_truncated_text_with_an_ellipsis() {
+        let layout = TextLayout {
+            max_height: Some(2),
+            ..Default::default()
+        };
+
+        assert_eq!(lines(&font(" abc…"), "a\nb\nc", &layout), ["a", "b…"]);
+        // Fonts without `…` get three dots instead.
+        assert_eq!(lines(&plain(), "a\nb\nc", &layout), ["a", "b..."]);
+    }
+
+    #[test]
+    fn cuts_long_lines_without_wrapping() {
+        let layout = TextLayout {
+            wrap: false,
+            ..wrapped(4)
+        };
+        let clip = TextLayout {
+            overflow: Overflow::Clip,
+            ..layout
+        };
+
+        assert_eq!(lines(&plain(), "abcdefg", &clip), ["abcd"]);
+        assert_eq!(lines(&font("abcdefg…"), "abcdefg", &layout), ["abc…"]);
+        assert_eq!(lines(&plain(), "abcdefg", &layout), ["a..."]);
+        assert_eq!(lines(&plain(), "abcd", &layout), ["abcd"]);
+    }
+
+    #[test]
+    fn measures_the_laid_out_box() {
+        let layout = TextLayout {
+            line_spacing: 1,
+            ..wrapped(3)
+        };
+        let size = measure_text(&plain(), "abcdefg", &layout);
+
+        assert_eq!((size.width, size.height), (3, 5));
+    }
+}
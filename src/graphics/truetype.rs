@@ -0,0 +1,327 @@
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use ab_glyph::{point, Font as _, FontVec, PxScale, ScaleFont as _};
+use anyhow::anyhow;
+
+use super::{Font, Glyph, GlyphMetrics};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rasterization {
+    /// Pixels whose coverage (0-255) reaches the threshold are drawn.
+    Threshold(u8),
+    /// 4x4 ordered dithering of the coverage, keeps anti-aliased edges and thin strokes.
+    Dither,
+}
+
+const BAYER_4X4: [u8; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+#[derive(Clone)]
+pub struct RasterizedGlyph {
+    pub metrics: GlyphMetrics,
+    pub bitmap: Vec<u8>,
+}
+
+/// Renders `chr` at `size` pixels (the distance from ascent to descent) into a 1-bit glyph
+/// in the same layout as [`Glyph`]. Returns `None` if the font has no glyph for `chr`.
+pub fn rasterize_glyph(
+    font: &FontVec,
+    chr: char,
+    size: u16,
+    rasterization: Rasterization,
+) -> Option<RasterizedGlyph> {
+    let id = font.glyph_id(chr);
+
+    if id.0 == 0 {
+        return None;
+    }
+
+    let advance = font
+        .as_scaled(PxScale::from(size as f32))
+        .h_advance(id)
+        .round()
+        .max(0.0) as u16;
+    let glyph = id.with_scale_and_position(size as f32, point(0.0, 0.0));
+
+    // Whitespace has no outline, only an advance.
+    let outline = match font.outline_glyph(glyph) {
+        Some(outline) => outline,
+        None => {
+            return Some(RasterizedGlyph {
+                metrics: GlyphMetrics {
+                    advance,
+                    ..Default::default()
+                },
+                bitmap: Vec::new(),
+            })
+        }
+    };
+
+    let bounds = outline.px_bounds();
+    let width = bounds.width() as u16;
+    let height = bounds.height() as u16;
+    let stride = ((width + 7) / 8) as usize;
+    let mut bitmap = vec![0u8; stride * height as usize];
+
+    outline.draw(|x, y, coverage| {
+        let set = match rasterization {
+            Rasterization::Threshold(level) => coverage > 0.0 && coverage * 255.0 >= level as f32,
+            Rasterization::Dither => {
+                coverage * 16.0 > BAYER_4X4[((y % 4) * 4 + x % 4) as usize] as f32 + 0.5
+            }
+        };
+
+        if set && x < width as u32 && y < height as u32 {
+            bitmap[y as usize * stride + x as usize / 8] |= 1 << (x % 8);
+        }
+    });
+
+    Some(RasterizedGlyph {
+        metrics: GlyphMetrics {
+            width,
+            height,
+            x_offset: bounds.min.x as i16,
+            y_offset: -bounds.min.y as i16,
+            advance,
+        },
+        bitmap,
+    })
+}
+
+/// Bytes of glyph bitmaps a [`TrueTypeFace`] keeps cached unless told otherwise.
+pub const DEFAULT_GLYPH_CACHE_BUDGET: usize = 16 * 1024;
+
+struct CacheEntry {
+    glyph: RasterizedGlyph,
+    last_used: Cell<u64>,
+}
+
+type GlyphCache = HashMap<(u16, char), CacheEntry>;
+
+/// A parsed TrueType or OpenType font. Glyphs are rasterized the first time they are drawn
+/// and kept in a cache keyed by pixel size and character. When the cached bitmaps would
+/// exceed the budget the least recently drawn glyphs are dropped.
+///
+/// Glyphs loaded with [`TrueTypeFace::preload`] are drawn straight from the cache. Those
+/// cached on first use can be evicted while the face is borrowed, so they are copied out.
+pub struct TrueTypeFace {
+    font: FontVec,
+    rasterization: Rasterization,
+    budget: usize,
+    used: Cell<usize>,
+    clock: Cell<u64>,
+    preloaded: GlyphCache,
+    drawn: RefCell<GlyphCache>,
+}
+
+impl TrueTypeFace {
+    pub fn from_bytes(data: Vec<u8>) -> anyhow::Result<Self> {
+        Ok(TrueTypeFace {
+            font: FontVec::try_from_vec(data).map_err(|_| anyhow!("Invalid TrueType font"))?,
+            rasterization: Rasterization::Threshold(128),
+            budget: DEFAULT_GLYPH_CACHE_BUDGET,
+            used: Cell::new(0),
+            clock: Cell::new(0),
+            preloaded: HashMap::new(),
+            drawn: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub fn load(file_path: &str) -> anyhow::Result<Self> {
+        Self::from_bytes(std::fs::read(file_path)?)
+            .map_err(|err| anyhow!("Invalid font {}: {}", file_path, err))
+    }
+
+    pub fn set_rasterization(&mut self, rasterization: Rasterization) {
+        self.rasterization = rasterization;
+        self.clear_cache();
+    }
+
+    pub fn cache_budget(&self) -> usize {
+        self.budget
+    }
+
+    pub fn cache_used(&self) -> usize {
+        self.used.get()
+    }
+
+    /// Limits the cached glyph bitmaps to `budget` bytes, dropping glyphs until they fit.
+    pub fn set_cache_budget(&mut self, budget: usize) {
+        self.budget = budget;
+
+        while self.used.get() > self.budget {
+            self.evict_least_recently_used();
+        }
+    }
+
+    /// Rasterizes the glyphs of `text` at `size` pixels into the cache, or moves them there
+    /// if they were cached when first drawn, so they are drawn without being copied.
+    pub fn preload(&mut self, size: u16, text: &str) {
+        for chr in text.chars() {
+            let key = (size, chr);
+
+            if let Some(entry) = self.preloaded.get(&key) {
+                entry.last_used.set(self.tick());
+                continue;
+            }
+
+            let glyph = match self.drawn.get_mut().remove(&key) {
+                Some(entry) => {
+                    self.used.set(self.used.get() - entry.glyph.bitmap.len());
+                    entry.glyph
+                }
+                None => match rasterize_glyph(&self.font, chr, size, self.rasterization) {
+                    Some(glyph) => glyph,
+                    None => continue,
+                },
+            };
+
+            if glyph.bitmap.len() > self.budget {
+                continue;
+            }
+
+            while self.used.get() + glyph.bitmap.len() > self.budget {
+                self.evict_least_recently_used();
+            }
+
+            self.used.set(self.used.get() + glyph.bitmap.len());
+            self.preloaded.insert(
+                key,
+                CacheEntry {
+                    glyph,
+                    last_used: Cell::new(self.tick()),
+                },
+            );
+        }
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.preloaded.clear();
+        self.drawn.get_mut().clear();
+        self.used.set(0);
+    }
+
+    /// The face at a pixel size, usable wherever a [`Font`] is expected.
+    pub fn sized(&self, size: u16) -> TrueTypeFont<'_> {
+        TrueTypeFont { face: self, size }
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.set(self.clock.get() + 1);
+        self.clock.get()
+    }
+
+    /// Caches a glyph rasterized on first use. Preloaded glyphs may be borrowed, so only
+    /// other glyphs drawn on first use make room for it.
+    fn insert_drawn(&self, key: (u16, char), glyph: &RasterizedGlyph) {
+        let mut drawn = self.drawn.borrow_mut();
+        let len = glyph.bitmap.len();
+
+        while self.used.get() + len > self.budget {
+            let oldest = drawn
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used.get())
+                .map(|(key, _)| *key);
+
+            match oldest.and_then(|key| drawn.remove(&key)) {
+                Some(entry) => self.used.set(self.used.get() - entry.glyph.bitmap.len()),
+                None => return,
+            }
+        }
+
+        self.used.set(self.used.get() + len);
+        drawn.insert(
+            key,
+            CacheEntry {
+                glyph: glyph.clone(),
+                last_used: Cell::new(self.tick()),
+            },
+        );
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let drawn = self.drawn.get_mut();
+        let oldest = self
+            .preloaded
+            .iter()
+            .map(|(key, entry)| (entry.last_used.get(), true, *key))
+            .chain(
+                drawn
+                    .iter()
+                    .map(|(key, entry)| (entry.last_used.get(), false, *key)),
+            )
+            .min();
+        let entry = match oldest {
+            Some((_, true, key)) => self.preloaded.remove(&key),
+            Some((_, false, key)) => drawn.remove(&key),
+            None => None,
+        };
+
+        if let Some(entry) = entry {
+            self.used.set(self.used.get() - entry.glyph.bitmap.len());
+        }
+    }
+}
+
+pub struct TrueTypeFont<'a> {
+    face: &'a TrueTypeFace,
+    size: u16,
+}
+
+impl Font for TrueTypeFont<'_> {
+    fn ascent(&self) -> u16 {
+        self.face
+            .font
+            .as_scaled(PxScale::from(self.size as f32))
+            .ascent()
+            .ceil() as u16
+    }
+
+    fn descent(&self) -> u16 {
+        (-self
+            .face
+            .font
+            .as_scaled(PxScale::from(self.size as f32))
+            .descent())
+        .ceil() as u16
+    }
+
+    fn glyph(&self, chr: char) -> Option<Glyph<'_>> {
+        let face = self.face;
+        let key = (self.size, chr);
+
+        if let Some(entry) = face.preloaded.get(&key) {
+            entry.last_used.set(face.tick());
+
+            return Some(Glyph {
+                metrics: entry.glyph.metrics,
+                bitmap: Cow::Borrowed(&entry.glyph.bitmap),
+            });
+        }
+
+        if let Some(entry) = face.drawn.borrow().get(&key) {
+            entry.last_used.set(face.tick());
+
+            return Some(Glyph {
+                metrics: entry.glyph.metrics,
+                bitmap: Cow::Owned(entry.glyph.bitmap.clone()),
+            });
+        }
+
+        let glyph = rasterize_glyph(&face.font, chr, self.size, face.rasterization)?;
+
+        face.insert_drawn(key, &glyph);
+
+        Some(Glyph {
+            metrics: glyph.metrics,
+            bitmap: Cow::Owned(glyph.bitmap),
+        })
+    }
+
+    fn kerning(&self, left: char, right: char) -> i16 {
+        let font = self.face.font.as_scaled(PxScale::from(self.size as f32));
+
+        font.kern(font.glyph_id(left), font.glyph_id(right)).round() as i16
+    }
+}
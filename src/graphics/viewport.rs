@@ -0,0 +1,234 @@
+use std::fs::File;
+
+use anyhow::anyhow;
+
+use crate::filesystem::{load_image, TextureReader};
+
+use super::{plot_line, Draw, Glyph, GlyphTransform, MonoGraphics, Print, Rect, SetPixel, Vect2D};
+
+/// A rectangle of the screen with its own coordinates, (0, 0) being its top left corner.
+/// Anything drawn through it is clipped to the rectangle, the screen's previous clip is
+/// restored when the viewport is dropped.
+pub struct Viewport<'v, 'a> {
+    graphics: &'v mut MonoGraphics<'a>,
+    origin: Vect2D,
+    width: u16,
+    height: u16,
+    previous_clip: Rect,
+}
+
+impl<'v, 'a> Viewport<'v, 'a> {
+    /// A viewport over `area` of the screen. Parts of `area` outside the current clip are not
+    /// drawn to, but still count towards the viewport's size.
+    pub fn new(graphics: &'v mut MonoGraphics<'a>, area: Rect) -> Self {
+        let previous_clip = graphics.clip();
+
+        graphics.set_clip(Some(area.intersection(&previous_clip).unwrap_or_default()));
+
+        Viewport {
+            graphics,
+            origin: area.position,
+            width: area.width,
+            height: area.height,
+            previous_clip,
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Where the viewport is on the screen.
+    pub fn bounds(&self) -> Rect {
+        Rect::new(self.origin, self.width, self.height)
+    }
+
+    /// A nested viewport over `area`, given in this viewport's coordinates.
+    pub fn viewport(&mut self, area: Rect) -> Viewport<'_, 'a> {
+        let position = self.to_screen(area.position);
+
+        Viewport::new(self.graphics, Rect::new(position, area.width, area.height))
+    }
+
+    /// Screen position of a point in the viewport, saturating at the edge of the coordinate
+    /// range, which is always outside the clip.
+    fn to_screen(&self, c: Vect2D) -> Vect2D {
+        Vect2D::new(
+            self.origin.x.saturating_add(c.x),
+            self.origin.y.saturating_add(c.y),
+        )
+    }
+
+    /// The visible part of `area`, in screen coordinates.
+    fn visible(&self, area: Rect) -> Option<Rect> {
+        Rect::new(self.to_screen(area.position), area.width, area.height)
+            .intersection(&self.graphics.clip())
+    }
+
+    fn fill(&mut self, area: Rect, color: bool) -> anyhow::Result<()> {
+        if let Some(area) = self.visible(area) {
+            for y in area.position.y..area.position.y + area.height {
+                self.graphics
+                    .draw_hline(Vect2D::new(area.position.x, y), area.width, color)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Viewport<'_, '_> {
+    fn drop(&mut self) {
+        self.graphics.set_clip(Some(self.previous_clip));
+    }
+}
+
+impl SetPixel<bool> for Viewport<'_, '_> {
+    /// Pixels outside the viewport are skipped.
+    fn set_pixel(&mut self, c: Vect2D, color: bool) -> anyhow::Result<()> {
+        let c = self.to_screen(c);
+
+        if !self.graphics.clip().contains(c) {
+            return Ok(());
+        }
+
+        self.graphics.set_pixel(c, color)
+    }
+}
+
+impl Draw<bool> for Viewport<'_, '_> {
+    /// Fills the viewport.
+    fn clear(&mut self, color: bool) -> anyhow::Result<()> {
+        self.graphics.clear(color)
+    }
+
+    /// Lines may leave the viewport, only the part inside it is drawn.
+    fn draw_line(&mut self, c1: Vect2D, c2: Vect2D, color: bool) -> anyhow::Result<()> {
+        plot_line(c1, c2, |c| self.set_pixel(c, color))
+    }
+
+    fn draw_hline(&mut self, c: Vect2D, len: u16, color: bool) -> anyhow::Result<()> {
+        self.fill(Rect::new(c, len, 1), color)
+    }
+
+    fn draw_vline(&mut self, c: Vect2D, height: u16, color: bool) -> anyhow::Result<()> {
+        self.fill(Rect::new(c, 1, height), color)
+    }
+
+    fn draw_rectangle(
+        &mut self,
+        corner1: Vect2D,
+        corner2: Vect2D,
+        color: bool,
+    ) -> anyhow::Result<()> {
+        let left = corner1.x.min(corner2.x);
+        let top = corner1.y.min(corner2.y);
+        let width = corner1.x.abs_diff(corner2.x).saturating_add(1);
+        let height = corner1.y.abs_diff(corner2.y).saturating_add(1);
+
+        self.draw_hline(Vect2D::new(left, top), width, color)?;
+        self.draw_hline(Vect2D::new(left, top + height - 1), width, color)?;
+        self.draw_vline(Vect2D::new(left, top), height, color)?;
+        self.draw_vline(Vect2D::new(left + width - 1, top), height, color)
+    }
+
+    fn fill_rectangle(
+        &mut self,
+        corner1: Vect2D,
+        corner2: Vect2D,
+        color: bool,
+    ) -> anyhow::Result<()> {
+        let area = Rect::new(
+            Vect2D::new(corner1.x.min(corner2.x), corner1.y.min(corner2.y)),
+            corner1.x.abs_diff(corner2.x),
+            corner1.y.abs_diff(corner2.y),
+        );
+
+        self.fill(area, color)
+    }
+
+    fn draw_texture(&mut self, corner: Vect2D, texture: &Vec<Vec<u8>>) -> anyhow::Result<()> {
+        self.graphics.draw_texture(self.to_screen(corner), texture)
+    }
+
+    fn draw_texture_from_flash(&mut self, corner: Vect2D, path: &str) -> anyhow::Result<()> {
+        let header = *TextureReader::new(File::open(path)?)
+            .map_err(|err| anyhow!("Invalid texture {}: {}", path, err))?
+            .header();
+
+        if self
+            .visible(Rect::new(corner, header.width, header.height))
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        self.graphics
+            .draw_texture_from_flash(self.to_screen(corner), path)
+    }
+
+    fn draw_image_from_flash(&mut self, corner: Vect2D, path: &str) -> anyhow::Result<()> {
+        let image = load_image(path)?;
+
+        if self
+            .visible(Rect::new(corner, image.width(), image.height()))
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        self.graphics
+            .draw_texture_data(self.to_screen(corner), &image)
+    }
+}
+
+impl Print<bool> for Viewport<'_, '_> {
+    fn put_char(
+        &mut self,
+        c: &Vect2D,
+        chr: char,
+        color: bool,
+        background: Option<bool>,
+    ) -> anyhow::Result<()> {
+        self.graphics
+            .put_char(&self.to_screen(*c), chr, color, background)
+    }
+
+    fn put_glyph(
+        &mut self,
+        c: &Vect2D,
+        glyph: &Glyph,
+        color: bool,
+        background: Option<bool>,
+    ) -> anyhow::Result<()> {
+        self.graphics
+            .put_glyph(&self.to_screen(*c), glyph, color, background)
+    }
+
+    fn put_glyph_transformed(
+        &mut self,
+        c: &Vect2D,
+        glyph: &Glyph,
+        transform: GlyphTransform,
+        color: bool,
+        background: Option<bool>,
+    ) -> anyhow::Result<()> {
+        self.graphics.put_glyph_transformed(
+            &self.to_screen(*c),
+            glyph,
+            transform,
+            color,
+            background,
+        )
+    }
+
+    fn fill_background(&mut self, area: &Rect, color: bool) -> anyhow::Result<()> {
+        let area = Rect::new(self.to_screen(area.position), area.width, area.height);
+
+        self.graphics.fill_background(&area, color)
+    }
+}
@@ -1,3 +1,4 @@
 pub mod display;
 pub mod filesystem;
 pub mod graphics;
+pub mod logging;
@@ -0,0 +1,168 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::graphics::{Console, Font, Print, Rect};
+
+/// Lines kept for the panel while it is not being rendered, older ones are dropped.
+const DEFAULT_CAPACITY: usize = 64;
+
+/// Log lines waiting to be shown, shared between the logger and the [`LogPanel`].
+pub struct LogBuffer {
+    lines: Mutex<VecDeque<(Level, String)>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        LogBuffer {
+            lines: Mutex::new(VecDeque::new()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn push(&self, level: Level, line: String) {
+        if let Ok(mut lines) = self.lines.lock() {
+            if lines.len() == self.capacity {
+                lines.pop_front();
+            }
+
+            lines.push_back((level, line));
+        }
+    }
+
+    pub fn drain(&self) -> Vec<(Level, String)> {
+        match self.lines.lock() {
+            Ok(mut lines) => lines.drain(..).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// A logger that passes every record on to `inner`, usually the serial logger, and copies
+/// records up to `display_level` into a [`LogBuffer`] for a [`LogPanel`] to draw.
+pub struct DisplayLogger<L> {
+    inner: L,
+    display_level: LevelFilter,
+    buffer: Arc<LogBuffer>,
+}
+
+impl<L: Log + 'static> DisplayLogger<L> {
+    pub fn new(inner: L, display_level: LevelFilter) -> Self {
+        DisplayLogger {
+            inner,
+            display_level,
+            buffer: Arc::new(LogBuffer::new(DEFAULT_CAPACITY)),
+        }
+    }
+
+    pub fn buffer(&self) -> Arc<LogBuffer> {
+        self.buffer.clone()
+    }
+
+    /// Sets this as the global logger, records above `max_level` are skipped by the `log`
+    /// macros before reaching either output. Returns the buffer to build a panel from.
+    pub fn install(self, max_level: LevelFilter) -> anyhow::Result<Arc<LogBuffer>> {
+        let buffer = self.buffer();
+
+        log::set_logger(Box::leak(Box::new(self))).map_err(|err| anyhow!("{}", err))?;
+        log::set_max_level(max_level);
+
+        Ok(buffer)
+    }
+}
+
+impl<L: Log> Log for DisplayLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.display_level || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+
+        if record.level() <= self.display_level {
+            self.buffer
+                .push(record.level(), format!("{}", record.args()));
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// A scrolling view of the log drawn into a region of the screen. Long lines wrap at the
+/// panel's edge, errors and warnings have their level marked in inverse video.
+pub struct LogPanel {
+    console: Console<'static>,
+    buffer: Arc<LogBuffer>,
+}
+
+impl LogPanel {
+    pub fn new(region: Rect, buffer: Arc<LogBuffer>) -> Self {
+        LogPanel {
+            console: Console::new(region),
+            buffer,
+        }
+    }
+
+    pub fn with_font(region: Rect, font: &'static dyn Font, buffer: Arc<LogBuffer>) -> Self {
+        LogPanel {
+            console: Console::with_font(region, font),
+            buffer,
+        }
+    }
+
+    pub fn region(&self) -> Rect {
+        self.console.region()
+    }
+
+    /// Forces the next render to redraw the whole panel, e.g. after the screen was cleared.
+    pub fn mark_dirty(&mut self) {
+        self.console.mark_dirty();
+    }
+
+    /// Moves new log lines into the panel. Returns whether there were any.
+    pub fn update(&mut self) -> bool {
+        let lines = self.buffer.drain();
+
+        for (level, line) in lines.iter() {
+            let tag = match level {
+                Level::Error => "\x1b[7mE\x1b[0m",
+                Level::Warn => "\x1b[7mW\x1b[0m",
+                Level::Info => "I",
+                Level::Debug => "D",
+                Level::Trace => "T",
+            };
+
+            // Start every record on a fresh line, without leaving an empty one at the bottom.
+            if self.console.cursor().0 > 0 {
+                self.console.put_char('\n');
+            }
+
+            let _ = write!(self.console, "{} {}", tag, line.trim_end());
+        }
+
+        !lines.is_empty()
+    }
+
+    /// Takes in new log lines and draws the rows that changed.
+    pub fn render<U, T>(
+        &mut self,
+        target: &mut U,
+        foreground: T,
+        background: T,
+    ) -> anyhow::Result<()>
+    where
+        U: Print<T>,
+        T: Clone,
+    {
+        self.update();
+        self.console.render(target, foreground, background)
+    }
+}
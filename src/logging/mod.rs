@@ -0,0 +1,3 @@
+pub mod display_logger;
+
+pub use display_logger::*;
@@ -1,99 +1,119 @@
-use anyhow::Result;
-use esp_idf_svc::hal::prelude::*;
-use esp_idf_svc::hal::{delay::Delay, peripherals::Peripherals};
-use esp_rs_extensa::display::SharpMemoryDisplay;
-use esp_rs_extensa::filesystem::register_spiffs_partition;
-use esp_rs_extensa::graphics::{Draw, MonoGraphics, Vect2D, BLACK, WHITE};
+#[cfg(target_os = "espidf")]
+fn main() -> anyhow::Result<()> {
+    firmware::main()
+}
 
-const MOUNT_POINT: &str = "/spiffs";
-const PARTITION_NAME: &str = "storage";
+// Only the library builds for other targets, to run its tests on the host.
+#[cfg(not(target_os = "espidf"))]
+fn main() {
+    eprintln!("The firmware only runs on the ESP32, build it for xtensa-esp32-espidf");
+}
 
-fn main() -> Result<()> {
-    // It is necessary to call this function once. Otherwise some patches to the runtime
-    // implemented by esp-idf-sys might not link properly. See https://github.com/esp-rs/esp-idf-template/issues/71
-    esp_idf_svc::sys::link_patches();
+#[cfg(target_os = "espidf")]
+mod firmware {
+    use anyhow::Result;
+    use esp_idf_svc::hal::prelude::*;
+    use esp_idf_svc::hal::{delay::Delay, peripherals::Peripherals};
+    use esp_rs_extensa::display::SharpMemoryDisplay;
+    use esp_rs_extensa::filesystem::register_spiffs_partition;
+    use esp_rs_extensa::graphics::{Draw, MonoGraphics, Rect, Vect2D, BLACK, WHITE};
+    use esp_rs_extensa::logging::{DisplayLogger, LogPanel};
+    use log::LevelFilter;
 
-    // Bind the log crate to the ESP Logging facilities
-    esp_idf_svc::log::EspLogger::initialize_default();
+    const MOUNT_POINT: &str = "/spiffs";
+    const PARTITION_NAME: &str = "storage";
 
-    let peripherals = Peripherals::take()?;
+    pub fn main() -> Result<()> {
+        // It is necessary to call this function once. Otherwise some patches to the runtime
+        // implemented by esp-idf-sys might not link properly. See https://github.com/esp-rs/esp-idf-template/issues/71
+        esp_idf_svc::sys::link_patches();
 
-    register_spiffs_partition(MOUNT_POINT, PARTITION_NAME)?;
+        // Bind the log crate to the ESP Logging facilities, and mirror it to the bottom of the screen
+        let log_buffer = DisplayLogger::new(esp_idf_svc::log::EspLogger::new(), LevelFilter::Info)
+            .install(LevelFilter::Info)?;
 
-    let mut display = SharpMemoryDisplay::new(
-        2.MHz().into(),
-        peripherals.pins.gpio25,
-        peripherals.pins.gpio26,
-        peripherals.pins.gpio27,
-        peripherals.spi3,
-    )?;
+        let peripherals = Peripherals::take()?;
 
-    let mut graphics = MonoGraphics::new(&mut display, 400, 240);
+        register_spiffs_partition(MOUNT_POINT, PARTITION_NAME)?;
 
-    log::info!("Hello, world!");
+        let mut display = SharpMemoryDisplay::new(
+            2.MHz().into(),
+            peripherals.pins.gpio25,
+            peripherals.pins.gpio26,
+            peripherals.pins.gpio27,
+            peripherals.spi3,
+        )?;
 
-    let delay: Delay = Default::default();
+        let mut graphics = MonoGraphics::new(&mut display, 400, 240);
+        let mut log_panel = LogPanel::new(Rect::new(Vect2D::new(0, 192), 400, 48), log_buffer);
 
-    loop {
-        //graphics.draw_rectangle(Vect2D { x: 20, y: 20 }, Vect2D { x: 380, y: 220 }, BLACK)?;
-        //graphics.fill_rectangle(Vect2D { x: 40, y: 40 }, Vect2D { x: 360, y: 200 }, BLACK)?;
-        /*for i in (0..80 as u16).step_by(3) {
-            graphics.draw_rectangle(
-                Vect2D {
-                    x: 20 + i,
-                    y: 20 + i,
-                },
-                Vect2D {
-                    x: 380 - i,
-                    y: 220 - i,
-                },
-                BLACK,
-            )?;
-        }
+        log::info!("Hello, world!");
 
+        let delay: Delay = Default::default();
 
+        loop {
+            //graphics.draw_rectangle(Vect2D { x: 20, y: 20 }, Vect2D { x: 380, y: 220 }, BLACK)?;
+            //graphics.fill_rectangle(Vect2D { x: 40, y: 40 }, Vect2D { x: 360, y: 200 }, BLACK)?;
+            /*for i in (0..80 as u16).step_by(3) {
+                graphics.draw_rectangle(
+                    Vect2D {
+                        x: 20 + i,
+                        y: 20 + i,
+                    },
+                    Vect2D {
+                        x: 380 - i,
+                        y: 220 - i,
+                    },
+                    BLACK,
+                )?;
+            }
 
-        graphics.draw_rectangle(Vect2D { x: 2, y: 2 }, Vect2D { x: 3, y: 3 }, BLACK)?;*/
-        /*for i in 1..20 as u16 {
-            graphics.draw_hline(Vect2D { x: 0, y: i }, i, BLACK)?;
-            graphics.draw_vline(Vect2D { x: 19 - i, y: 20 }, i, BLACK)?;
-        }*/
-        graphics.draw_texture_from_flash(Vect2D::new(0, 0), "/spiffs/land.img")?;
-        // graphics.draw_hline(Vect2D::new(10, 10), 40, BLACK)?;
-        graphics.draw()?;
-        log::info!("draw display");
-        delay.delay_us(1000000);
-        /*display.set_pixel(0, 10, false)?;
-        display.set_pixel(0, 11, false)?;
-        display.set_pixel(2, 10, false)?;
-        display.set_pixel(2, 11, false)?;
-        display.set_pixel(4, 10, false)?;
-        display.set_pixel(4, 11, false)?;
-        display.set_pixel(6, 10, false)?;
-        display.set_pixel(6, 11, false)?;
-        display.set_pixel(8, 10, false)?;
-        display.set_pixel(8, 11, false)?;
-        display.set_pixel(10, 10, false)?;
-        display.set_pixel(10, 11, false)?;
-        display.set_pixel(12, 10, false)?;
-        display.set_pixel(12, 11, false)?;
-        display.set_pixel(14, 10, false)?;
-        display.set_pixel(14, 11, false)?;
-        display.set_pixel(16, 10, false)?;
-        display.set_pixel(16, 11, false)?;
-        display.refresh()?;
-        led.set_high()?;
-        log::info!("Set High!");
-        delay.delay_us(1000000);
-        /*display.set_pixel(10, 10, false)?;
-        display.set_pixel(10, 11, false)?;
-        display.set_pixel(11, 10, false)?;
-        display.set_pixel(11, 11, false)?;
-        display.refresh()?;*/
-        led.set_low()?;*/
-        graphics.clear(WHITE)?;
-        //graphics.clear_display()?;
-        log::info!("Clear display");
-        delay.delay_us(1000000);
+
+
+            graphics.draw_rectangle(Vect2D { x: 2, y: 2 }, Vect2D { x: 3, y: 3 }, BLACK)?;*/
+            /*for i in 1..20 as u16 {
+                graphics.draw_hline(Vect2D { x: 0, y: i }, i, BLACK)?;
+                graphics.draw_vline(Vect2D { x: 19 - i, y: 20 }, i, BLACK)?;
+            }*/
+            graphics.draw_texture_from_flash(Vect2D::new(0, 0), "/spiffs/land.img")?;
+            log_panel.mark_dirty();
+            log_panel.render(&mut graphics, BLACK, WHITE)?;
+            // graphics.draw_hline(Vect2D::new(10, 10), 40, BLACK)?;
+            graphics.draw()?;
+            log::info!("draw display");
+            delay.delay_us(1000000);
+            /*display.set_pixel(0, 10, false)?;
+            display.set_pixel(0, 11, false)?;
+            display.set_pixel(2, 10, false)?;
+            display.set_pixel(2, 11, false)?;
+            display.set_pixel(4, 10, false)?;
+            display.set_pixel(4, 11, false)?;
+            display.set_pixel(6, 10, false)?;
+            display.set_pixel(6, 11, false)?;
+            display.set_pixel(8, 10, false)?;
+            display.set_pixel(8, 11, false)?;
+            display.set_pixel(10, 10, false)?;
+            display.set_pixel(10, 11, false)?;
+            display.set_pixel(12, 10, false)?;
+            display.set_pixel(12, 11, false)?;
+            display.set_pixel(14, 10, false)?;
+            display.set_pixel(14, 11, false)?;
+            display.set_pixel(16, 10, false)?;
+            display.set_pixel(16, 11, false)?;
+            display.refresh()?;
+            led.set_high()?;
+            log::info!("Set High!");
+            delay.delay_us(1000000);
+            /*display.set_pixel(10, 10, false)?;
+            display.set_pixel(10, 11, false)?;
+            display.set_pixel(11, 10, false)?;
+            display.set_pixel(11, 11, false)?;
+            display.refresh()?;*/
+            led.set_low()?;*/
+            graphics.clear(WHITE)?;
+            //graphics.clear_display()?;
+            log::info!("Clear display");
+            delay.delay_us(1000000);
+        }
     }
 }
@@ -0,0 +1,362 @@
+//! Renders scenes through `MonoGraphics` into a `MemoryDisplay` and compares them with the
+//! reference images in `tests/golden`. After an intended change in rendering, update the
+//! references with `UPDATE_GOLDEN=1` and check the new images before committing them.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use esp_rs_extensa::display::MemoryDisplay;
+use esp_rs_extensa::filesystem::encode_pbm;
+use esp_rs_extensa::graphics::{
+    Draw, MonoGraphics, Overflow, Print, Printer, RasterOp, Rect, Rotation, SetPixel, Vect2D,
+    Viewport, BLACK, WHITE,
+};
+
+fn render<F>(width: u16, height: u16, scene: F) -> MemoryDisplay
+where
+    F: FnOnce(&mut MonoGraphics) -> anyhow::Result<()>,
+{
+    let mut display = MemoryDisplay::new(width, height);
+    let mut graphics = MonoGraphics::new(&mut display, width, height);
+
+    scene(&mut graphics).unwrap();
+    graphics.draw().unwrap();
+    drop(graphics);
+
+    display
+}
+
+fn assert_golden(name: &str, display: &MemoryDisplay) {
+    let rows: Vec<u8> = display.frame.iter().flatten().copied().collect();
+    let actual = encode_pbm(display.width, display.height, &rows);
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.pbm", name));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read(&path).unwrap_or_else(|err| {
+        panic!(
+            "Missing reference {}: {}, run with UPDATE_GOLDEN=1 to create it",
+            path.display(),
+            err
+        )
+    });
+
+    if actual == expected {
+        return;
+    }
+
+    let actual_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.pbm", name));
+    std::fs::write(&actual_path, &actual).unwrap();
+
+    panic!(
+        "{} does not match its reference, the rendered image is in {}\n{}",
+        name,
+        actual_path.display(),
+        diff(display, &expected)
+    );
+}
+
+/// Draws the differing part of the image: `#` and `.` are matching black and white pixels,
+/// `+` is black where white was expected and `-` white where black was expected.
+fn diff(display: &MemoryDisplay, expected: &[u8]) -> String {
+    let (width, height, rows) = match decode_pbm(expected) {
+        Some(image) => image,
+        None => return "The reference is not a binary PBM file".to_string(),
+    };
+
+    if (width, height) != (display.width, display.height) {
+        return format!(
+            "The reference is {}x{}, the rendered image {}x{}",
+            width, height, display.width, display.height
+        );
+    }
+
+    let stride = ((width + 7) / 8) as usize;
+    let expected_pixel =
+        |x: u16, y: u16| rows[y as usize * stride + (x / 8) as usize] & (0x80 >> (x % 8)) == 0;
+    let differs = |x: u16, y: u16| display.pixel(x, y) != Some(expected_pixel(x, y));
+
+    let (mut left, mut top, mut right, mut bottom) = (width, height, 0, 0);
+    for y in 0..height {
+        for x in 0..width {
+            if differs(x, y) {
+                left = left.min(x);
+                top = top.min(y);
+                right = right.max(x + 1);
+                bottom = bottom.max(y + 1);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Differences between ({}, {}) and ({}, {}):",
+        left,
+        top,
+        right - 1,
+        bottom - 1
+    );
+
+    for y in top.saturating_sub(1)..(bottom + 1).min(height) {
+        for x in left.saturating_sub(1)..(right + 1).min(width) {
+            out.push(
+                match (expected_pixel(x, y), display.pixel(x, y) == Some(WHITE)) {
+                    (WHITE, true) => '.',
+                    (BLACK, false) => '#',
+                    (WHITE, false) => '+',
+                    (BLACK, true) => '-',
+                },
+            );
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
+
+fn decode_pbm(bytes: &[u8]) -> Option<(u16, u16, &[u8])> {
+    let mut parts = bytes.splitn(3, |&byte| byte == b'\n');
+
+    if parts.next()? != b"P4" {
+        return None;
+    }
+
+    let (width, height) = std::str::from_utf8(parts.next()?).ok()?.split_once(' ')?;
+    let (width, height): (u16, u16) = (width.parse().ok()?, height.parse().ok()?);
+    let data = parts.next()?;
+
+    (data.len() == ((width + 7) / 8) as usize * height as usize).then_some((width, height, data))
+}
+
+#[test]
+fn horizontal_lines() {
+    // Every start offset within a byte with lengths up to two bytes, for the edge masks.
+    let display = render(32, 8 * 17, |graphics| {
+        for x in 0..8 {
+            for len in 1..=17 {
+                graphics.draw_hline(Vect2D::new(x, x * 17 + len - 1), len, BLACK)?;
+            }
+        }
+
+        Ok(())
+    });
+
+    assert_golden("horizontal_lines", &display);
+}
+
+#[test]
+fn vertical_lines() {
+    let display = render(40, 24, |graphics| {
+        for x in 0..20 {
+            graphics.draw_vline(Vect2D::new(x * 2, x % 5), x + 1, BLACK)?;
+        }
+
+        Ok(())
+    });
+
+    assert_golden("vertical_lines", &display);
+}
+
+#[test]
+fn rectangles() {
+    let display = render(64, 40, |graphics| {
+        graphics.draw_rectangle(Vect2D::new(1, 1), Vect2D::new(2, 2), BLACK)?;
+        graphics.draw_rectangle(Vect2D::new(5, 1), Vect2D::new(7, 3), BLACK)?;
+        graphics.draw_rectangle(Vect2D::new(10, 1), Vect2D::new(30, 12), BLACK)?;
+        graphics.fill_rectangle(Vect2D::new(13, 4), Vect2D::new(28, 10), BLACK)?;
+        graphics.fill_rectangle(Vect2D::new(33, 1), Vect2D::new(34, 2), BLACK)?;
+        graphics.fill_rectangle(Vect2D::new(37, 3), Vect2D::new(63, 39), BLACK)?;
+        graphics.fill_rectangle(Vect2D::new(40, 6), Vect2D::new(60, 36), WHITE)?;
+        graphics.draw_rectangle(Vect2D::new(0, 15), Vect2D::new(33, 39), BLACK)?;
+
+        Ok(())
+    });
+
+    assert_golden("rectangles", &display);
+}
+
+#[test]
+fn rectangle_corners() {
+    // The same outline from each pair of opposite corners, then the smallest rectangles.
+    let display = render(48, 16, |graphics| {
+        graphics.draw_rectangle(Vect2D::new(1, 1), Vect2D::new(8, 6), BLACK)?;
+        graphics.draw_rectangle(Vect2D::new(18, 6), Vect2D::new(11, 1), BLACK)?;
+        graphics.draw_rectangle(Vect2D::new(28, 1), Vect2D::new(21, 6), BLACK)?;
+        graphics.draw_rectangle(Vect2D::new(31, 6), Vect2D::new(38, 1), BLACK)?;
+        graphics.draw_rectangle(Vect2D::new(2, 10), Vect2D::new(2, 10), BLACK)?;
+        graphics.draw_rectangle(Vect2D::new(5, 10), Vect2D::new(6, 11), BLACK)?;
+        graphics.draw_rectangle(Vect2D::new(14, 10), Vect2D::new(9, 10), BLACK)?;
+        graphics.draw_rectangle(Vect2D::new(17, 14), Vect2D::new(17, 9), BLACK)?;
+        graphics.fill_rectangle(Vect2D::new(20, 10), Vect2D::new(21, 11), BLACK)?;
+
+        Ok(())
+    });
+
+    assert_golden("rectangle_corners", &display);
+}
+
+#[test]
+fn line_endpoints() {
+    // Lines from the centre in eight directions, a one pixel line and a diagonal from (2, 2).
+    let display = render(32, 24, |graphics| {
+        let centre = Vect2D::new(12, 12);
+
+        for end in [
+            Vect2D::new(20, 12),
+            Vect2D::new(20, 17),
+            Vect2D::new(15, 20),
+            Vect2D::new(12, 20),
+            Vect2D::new(4, 16),
+            Vect2D::new(4, 12),
+            Vect2D::new(9, 4),
+            Vect2D::new(20, 6),
+        ] {
+            graphics.draw_line(centre, end, BLACK)?;
+        }
+
+        graphics.draw_line(Vect2D::new(26, 20), Vect2D::new(26, 20), BLACK)?;
+        graphics.draw_line(Vect2D::new(2, 2), Vect2D::new(5, 5), BLACK)
+    });
+
+    assert_golden("line_endpoints", &display);
+}
+
+#[test]
+fn diagonal_lines() {
+    let display = render(48, 48, |graphics| {
+        graphics.draw_line(Vect2D::new(0, 0), Vect2D::new(47, 47), BLACK)?;
+        graphics.draw_line(Vect2D::new(0, 47), Vect2D::new(47, 0), BLACK)?;
+        graphics.draw_line(Vect2D::new(10, 0), Vect2D::new(20, 47), BLACK)?;
+        graphics.draw_line(Vect2D::new(0, 30), Vect2D::new(47, 20), BLACK)?;
+
+        Ok(())
+    });
+
+    assert_golden("diagonal_lines", &display);
+}
+
+#[test]
+fn text() {
+    let display = render(120, 64, |graphics| {
+        let mut printer = Printer::new(Vect2D::new(1, 1), BLACK);
+        printer.print(graphics, "Hello, 123!\nČšž ÄÖ €")?;
+
+        printer.set_background(Some(BLACK));
+        printer.set_color(WHITE);
+        printer.set_position(Vect2D::new(1, 20));
+        printer.print(graphics, "inverse")?;
+
+        printer.set_background(None);
+        printer.set_color(BLACK);
+        printer.set_scale(2);
+        printer.set_position(Vect2D::new(1, 32));
+        printer.print(graphics, "x2")?;
+
+        printer.set_scale(1);
+        printer.set_rotation(Rotation::Deg90);
+        printer.set_position(Vect2D::new(110, 1));
+        printer.print(graphics, "down")?;
+
+        printer.set_rotation(Rotation::Deg0);
+        printer.print_in(
+            graphics,
+            "wrapped into a small box",
+            Rect::new(Vect2D::new(40, 32), 60, 24),
+            Overflow::Ellipsis,
+        )
+    });
+
+    assert_golden("text", &display);
+}
+
+fn orientation_scene(graphics: &mut MonoGraphics) -> anyhow::Result<()> {
+    graphics.draw_rectangle(Vect2D::new(0, 0), Vect2D::new(graphics.width - 1, 9), BLACK)?;
+    graphics.put_char(&Vect2D::new(2, 1), 'F', BLACK, None)?;
+    graphics.fill_rectangle(Vect2D::new(3, 12), Vect2D::new(9, 20), BLACK)?;
+    graphics.draw_line(Vect2D::new(12, 12), Vect2D::new(20, 22), BLACK)?;
+    graphics.set_pixel(Vect2D::new(graphics.width - 1, graphics.height - 1), BLACK)
+}
+
+#[test]
+fn orientations() {
+    for (name, rotation, flip) in [
+        ("rotation_90", Rotation::Deg90, false),
+        ("rotation_180", Rotation::Deg180, false),
+        ("rotation_270_flipped", Rotation::Deg270, true),
+    ] {
+        let display = render(40, 24, |graphics| {
+            graphics.set_rotation(rotation);
+            graphics.set_flip(flip, false);
+            orientation_scene(graphics)
+        });
+
+        assert_golden(name, &display);
+    }
+}
+
+#[test]
+fn bitmaps() {
+    let mut sprite = MonoGraphics::offscreen(11, 7);
+    sprite.clear(BLACK).unwrap();
+    sprite
+        .fill_rectangle(Vect2D::new(2, 2), Vect2D::new(9, 5), WHITE)
+        .unwrap();
+
+    let display = render(64, 24, |graphics| {
+        graphics.fill_rectangle(Vect2D::new(32, 0), Vect2D::new(64, 24), BLACK)?;
+
+        for (i, op) in [
+            RasterOp::Copy,
+            RasterOp::CopyInverted,
+            RasterOp::And,
+            RasterOp::Or,
+            RasterOp::Xor,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let x = 3 + i as u16 * 12;
+            graphics.blit(
+                &sprite,
+                Rect::new(Vect2D::new(0, 0), 11, 7),
+                Vect2D::new(x, 2),
+                op,
+            )?;
+        }
+
+        let arrow = [0x18, 0x3C, 0x7E, 0xFF, 0x18, 0x18];
+        graphics.draw_bitmap(Vect2D::new(5, 14), &arrow, 8, 6)?;
+        graphics.draw_bitmap(Vect2D::new(59, 14), &arrow, 8, 6)
+    });
+
+    assert_golden("bitmaps", &display);
+}
+
+#[test]
+fn viewports_and_scrolling() {
+    let display = render(64, 32, |graphics| {
+        for y in (0..32).step_by(4) {
+            graphics.draw_hline(Vect2D::new(0, y), 64, BLACK)?;
+        }
+
+        graphics.scroll_region(Rect::new(Vect2D::new(0, 0), 64, 16), 0, 2, WHITE)?;
+        graphics.scroll_region(Rect::new(Vect2D::new(5, 16), 20, 16), -3, 1, BLACK)?;
+        graphics.invert_region(&Rect::new(Vect2D::new(40, 20), 20, 8));
+
+        let mut viewport = Viewport::new(graphics, Rect::new(Vect2D::new(30, 4), 30, 12));
+        viewport.clear(WHITE)?;
+        viewport.draw_rectangle(Vect2D::new(0, 0), Vect2D::new(29, 11), BLACK)?;
+        viewport.draw_line(Vect2D::new(0, 0), Vect2D::new(40, 20), BLACK)?;
+        viewport.put_char(&Vect2D::new(26, 3), 'W', BLACK, Some(WHITE))
+    });
+
+    assert_golden("viewports_and_scrolling", &display);
+}
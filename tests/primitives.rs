@@ -0,0 +1,358 @@
+//! Checks that the optimized drawing paths of `MonoGraphics`, which write whole bytes of the
+//! framebuffer at a time, leave the same pixels as plotting every pixel with `set_pixel`.
+//! Screens are random in size, orientation, clip and contents, and coordinates lean towards
+//! byte boundaries, where the partial bytes at the ends of a span are masked.
+
+use esp_rs_extensa::filesystem::Texture;
+use esp_rs_extensa::graphics::{Draw, MonoGraphics, RasterOp, Rect, Rotation, SetPixel, Vect2D};
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+
+#[derive(Clone, Debug)]
+struct Screen {
+    width: u16,
+    height: u16,
+    rotation: Rotation,
+    flip: (bool, bool),
+    clip: Option<Rect>,
+    pixels: Vec<bool>,
+}
+
+impl Screen {
+    /// Size after rotating, the range of coordinates drawn to.
+    fn logical_size(&self) -> (u16, u16) {
+        match self.rotation {
+            Rotation::Deg90 | Rotation::Deg270 => (self.height, self.width),
+            Rotation::Deg0 | Rotation::Deg180 => (self.width, self.height),
+        }
+    }
+
+    fn build(&self) -> MonoGraphics<'static> {
+        let mut graphics = MonoGraphics::offscreen(self.width, self.height);
+        let (width, _) = self.logical_size();
+
+        graphics.set_rotation(self.rotation);
+        graphics.set_flip(self.flip.0, self.flip.1);
+
+        for (i, &pixel) in self.pixels.iter().enumerate() {
+            let c = Vect2D::new(i as u16 % width, i as u16 / width);
+
+            graphics.set_pixel(c, pixel).unwrap();
+        }
+
+        graphics.set_clip(self.clip);
+        graphics
+    }
+
+    /// The screen to draw to with the primitive under test, and one to plot the expected
+    /// pixels on.
+    fn pair(&self) -> (MonoGraphics<'static>, MonoGraphics<'static>) {
+        (self.build(), self.build())
+    }
+}
+
+fn rotation() -> impl Strategy<Value = Rotation> {
+    prop_oneof![
+        3 => Just(Rotation::Deg0),
+        1 => Just(Rotation::Deg90),
+        1 => Just(Rotation::Deg180),
+        1 => Just(Rotation::Deg270),
+    ]
+}
+
+/// A coordinate up to and including `max`, often right next to a multiple of 8.
+fn coordinate(max: u16) -> impl Strategy<Value = u16> {
+    prop_oneof![
+        0..=max,
+        (0..=max / 8, -1i32..=1)
+            .prop_map(move |(byte, offset)| (byte as i32 * 8 + offset).clamp(0, max as i32) as u16),
+    ]
+}
+
+/// A start and length within `0..limit`.
+fn span(limit: u16) -> impl Strategy<Value = (u16, u16)> {
+    (coordinate(limit - 1), coordinate(limit - 1)).prop_map(|(a, b)| (a.min(b), a.abs_diff(b) + 1))
+}
+
+fn clip(width: u16, height: u16) -> impl Strategy<Value = Option<Rect>> {
+    prop_oneof![
+        3 => Just(None),
+        1 => (span(width), span(height)).prop_map(|((x, width), (y, height))| {
+            Some(Rect::new(Vect2D::new(x, y), width, height))
+        }),
+    ]
+}
+
+/// Mostly untransformed and unclipped screens, which take the fast paths, up to 10 bytes wide.
+fn screen() -> impl Strategy<Value = Screen> {
+    (1..=80u16, 1..=24u16, rotation(), any::<(bool, bool)>())
+        .prop_flat_map(|(width, height, rotation, flip)| {
+            let (logical_width, logical_height) = match rotation {
+                Rotation::Deg90 | Rotation::Deg270 => (height, width),
+                Rotation::Deg0 | Rotation::Deg180 => (width, height),
+            };
+            let flip = prop_oneof![3 => Just((false, false)), 1 => Just(flip)];
+
+            (
+                Just((width, height, rotation)),
+                flip,
+                clip(logical_width, logical_height),
+                prop::collection::vec(any::<bool>(), width as usize * height as usize),
+            )
+        })
+        .prop_map(|((width, height, rotation), flip, clip, pixels)| Screen {
+            width,
+            height,
+            rotation,
+            flip,
+            clip,
+            pixels,
+        })
+}
+
+/// Packed 1-bit rows of `ceil(width / 8)` bytes, with random bits in the padding too.
+fn bitmap() -> impl Strategy<Value = (u16, u16, Vec<u8>)> {
+    (1..=20u16, 1..=12u16).prop_flat_map(|(width, height)| {
+        let len = ((width + 7) / 8) as usize * height as usize;
+
+        (
+            Just(width),
+            Just(height),
+            prop::collection::vec(any::<u8>(), len),
+        )
+    })
+}
+
+fn bit(bitmap: &[u8], stride: usize, x: u16, y: u16) -> bool {
+    bitmap[y as usize * stride + (x / 8) as usize] & (1 << (x % 8)) != 0
+}
+
+fn assert_same(actual: &MonoGraphics, expected: &MonoGraphics) -> Result<(), TestCaseError> {
+    for y in 0..expected.height {
+        for x in 0..expected.width {
+            let c = Vect2D::new(x, y);
+
+            prop_assert_eq!(actual.pixel(c), expected.pixel(c), "pixel ({}, {})", x, y);
+        }
+    }
+
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn hline_matches_pixels(
+        (screen, (x, len), y, color) in screen().prop_flat_map(|screen| {
+            let (width, height) = screen.logical_size();
+
+            (Just(screen), span(width), 0..height, any::<bool>())
+        })
+    ) {
+        let (mut actual, mut expected) = screen.pair();
+
+        actual.draw_hline(Vect2D::new(x, y), len, color).unwrap();
+
+        for x in x..x + len {
+            expected.set_pixel(Vect2D::new(x, y), color).unwrap();
+        }
+
+        assert_same(&actual, &expected)?;
+    }
+
+    #[test]
+    fn vline_matches_pixels(
+        (screen, x, (y, len), color) in screen().prop_flat_map(|screen| {
+            let (width, height) = screen.logical_size();
+
+            (Just(screen), 0..width, span(height), any::<bool>())
+        })
+    ) {
+        let (mut actual, mut expected) = screen.pair();
+
+        actual.draw_vline(Vect2D::new(x, y), len, color).unwrap();
+
+        for y in y..y + len {
+            expected.set_pixel(Vect2D::new(x, y), color).unwrap();
+        }
+
+        assert_same(&actual, &expected)?;
+    }
+
+    #[test]
+    fn fill_rectangle_matches_pixels(
+        (screen, (left, right), (top, bottom), color) in screen().prop_flat_map(|screen| {
+            let (width, height) = screen.logical_size();
+            // The bottom right corner is excluded, so it may be just past the screen.
+            let corners = |max| {
+                (coordinate(max), coordinate(max)).prop_map(|(a, b)| (a.min(b), a.max(b)))
+            };
+
+            (Just(screen), corners(width), corners(height), any::<bool>())
+        })
+    ) {
+        let (mut actual, mut expected) = screen.pair();
+
+        actual
+            .fill_rectangle(Vect2D::new(left, top), Vect2D::new(right, bottom), color)
+            .unwrap();
+
+        for y in top..bottom {
+            for x in left..right {
+                expected.set_pixel(Vect2D::new(x, y), color).unwrap();
+            }
+        }
+
+        assert_same(&actual, &expected)?;
+    }
+
+    #[test]
+    fn texture_matches_pixels(
+        (screen, (width, height, data), x, y) in screen().prop_flat_map(|screen| {
+            let (width, height) = screen.logical_size();
+
+            (Just(screen), bitmap(), coordinate(width - 1), coordinate(height - 1))
+        })
+    ) {
+        let (mut actual, mut expected) = screen.pair();
+        let texture = Texture::new(width, height, data).unwrap();
+
+        actual.draw_texture_data(Vect2D::new(x, y), &texture).unwrap();
+
+        for row in 0..height.min(expected.height - y) {
+            for column in 0..width.min(expected.width - x) {
+                let color = bit(&texture.data, texture.stride(), column, row);
+
+                expected.set_pixel(Vect2D::new(x + column, y + row), color).unwrap();
+            }
+        }
+
+        assert_same(&actual, &expected)?;
+    }
+
+    #[test]
+    fn texture_rows_match_pixels(
+        (screen, rows, x, y) in screen().prop_flat_map(|screen| {
+            let (width, height) = screen.logical_size();
+            // Rows of different lengths, some empty and some past the edge of the screen.
+            let row = prop::collection::vec(any::<u8>(), 0..=4);
+            let rows = prop::collection::vec(row, 0..=12);
+
+            (Just(screen), rows, coordinate(width - 1), coordinate(height - 1))
+        })
+    ) {
+        let (mut actual, mut expected) = screen.pair();
+
+        actual.draw_texture(Vect2D::new(x, y), &rows).unwrap();
+
+        for (row, bytes) in rows.iter().enumerate().take((expected.height - y) as usize) {
+            for column in 0..(bytes.len() as u16 * 8).min(expected.width - x) {
+                let color = bit(bytes, 0, column, 0);
+
+                expected.set_pixel(Vect2D::new(x + column, y + row as u16), color).unwrap();
+            }
+        }
+
+        assert_same(&actual, &expected)?;
+    }
+
+    #[test]
+    fn scroll_region_matches_pixels(
+        (screen, (x, width), (y, height), dx, dy, fill) in screen().prop_flat_map(|screen| {
+            let (width, height) = screen.logical_size();
+            let distance = |limit: u16| {
+                prop_oneof![
+                    -(limit as i16)..=limit as i16,
+                    coordinate(limit).prop_flat_map(|d| prop_oneof![Just(d as i16), Just(-(d as i16))]),
+                ]
+            };
+
+            (Just(screen), span(width), span(height), distance(width), distance(height), any::<bool>())
+        })
+    ) {
+        let (mut actual, mut expected) = screen.pair();
+        let area = Rect::new(Vect2D::new(x, y), width, height);
+
+        actual.scroll_region(area, dx, dy, fill).unwrap();
+
+        if let Some(area) = area.intersection(&expected.clip()) {
+            let before = screen.build();
+            let inside = |x: i32, y: i32| {
+                x >= area.position.x as i32
+                    && x < (area.position.x + area.width) as i32
+                    && y >= area.position.y as i32
+                    && y < (area.position.y + area.height) as i32
+            };
+
+            for y in area.position.y..area.position.y + area.height {
+                for x in area.position.x..area.position.x + area.width {
+                    let (from_x, from_y) = (x as i32 - dx as i32, y as i32 - dy as i32);
+                    let color = if inside(from_x, from_y) {
+                        before.pixel(Vect2D::new(from_x as u16, from_y as u16)).unwrap()
+                    } else {
+                        fill
+                    };
+
+                    expected.set_pixel(Vect2D::new(x, y), color).unwrap();
+                }
+            }
+        }
+
+        assert_same(&actual, &expected)?;
+    }
+
+    #[test]
+    fn blit_matches_pixels(
+        (screen, (width, height, data), area, x, y, op) in screen().prop_flat_map(|screen| {
+            let (width, height) = screen.logical_size();
+            // The area may reach past the source, which is clipped.
+            let source = bitmap().prop_flat_map(|(width, height, data)| {
+                let area = (span(width + 4), span(height + 4)).prop_map(|((x, w), (y, h))| {
+                    Rect::new(Vect2D::new(x, y), w, h)
+                });
+
+                (Just((width, height, data)), area)
+            });
+            let op = prop_oneof![
+                Just(RasterOp::Copy),
+                Just(RasterOp::CopyInverted),
+                Just(RasterOp::And),
+                Just(RasterOp::Or),
+                Just(RasterOp::Xor),
+            ];
+
+            (Just(screen), source, coordinate(width - 1), coordinate(height - 1), op)
+                .prop_map(|(screen, (bitmap, area), x, y, op)| (screen, bitmap, area, x, y, op))
+        })
+    ) {
+        let stride = ((width + 7) / 8) as usize;
+        let mut sprite = MonoGraphics::offscreen(width, height);
+
+        for row in 0..height {
+            for column in 0..width {
+                let color = bit(&data, stride, column, row);
+
+                sprite.set_pixel(Vect2D::new(column, row), color).unwrap();
+            }
+        }
+
+        let (mut actual, mut expected) = screen.pair();
+
+        actual.blit(&sprite, area, Vect2D::new(x, y), op).unwrap();
+
+        if let Some(area) = area.intersection(&Rect::new(Vect2D::new(0, 0), width, height)) {
+            for row in 0..area.height.min(expected.height - y) {
+                for column in 0..area.width.min(expected.width - x) {
+                    let c = Vect2D::new(x + column, y + row);
+                    let source = bit(&data, stride, area.position.x + column, area.position.y + row);
+                    let destination = expected.pixel(c).unwrap();
+
+                    expected
+                        .set_pixel(c, op.apply(source as u8, destination as u8) & 1 != 0)
+                        .unwrap();
+                }
+            }
+        }
+
+        assert_same(&actual, &expected)?;
+    }
+}
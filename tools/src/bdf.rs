@@ -0,0 +1,198 @@
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, Context};
+
+use crate::graphics::{BitmapFont, BitmapGlyph, GlyphMetrics};
+
+/// Parses a BDF font, keeping only characters inside one of `ranges` (all of them when
+/// `ranges` is empty).
+pub fn parse_bdf(
+    text: &str,
+    ranges: &[RangeInclusive<u32>],
+) -> anyhow::Result<BitmapFont<'static>> {
+    let mut ascent = None;
+    let mut descent = None;
+    let mut bounding_box = None;
+    let mut glyphs = Vec::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((number, line)) = lines.next() {
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("FONT_ASCENT") => ascent = Some(parse_number(words.next(), number)?),
+            Some("FONT_DESCENT") => descent = Some(parse_number(words.next(), number)?),
+            Some("FONTBOUNDINGBOX") => {
+                let values = parse_numbers(words, 4, number)?;
+                bounding_box = Some((values[1], values[3]));
+            }
+            Some("STARTCHAR") => {
+                let mut encoding = -1;
+                let mut advance = 0;
+                let mut bbx = [0i32; 4];
+
+                for (number, line) in lines.by_ref() {
+                    let mut words = line.split_whitespace();
+
+                    match words.next() {
+                        Some("ENCODING") => encoding = parse_number(words.next(), number)?,
+                        Some("DWIDTH") => advance = parse_number(words.next(), number)?,
+                        Some("BBX") => bbx.copy_from_slice(&parse_numbers(words, 4, number)?),
+                        Some("BITMAP") => break,
+                        _ => {}
+                    }
+                }
+
+                let [width, height, x_offset, y_offset] = bbx;
+                let stride = ((width + 7) / 8) as usize;
+                let mut rows = Vec::with_capacity(stride * height as usize);
+
+                for _ in 0..height {
+                    let (number, row) = lines
+                        .next()
+                        .ok_or_else(|| anyhow!("BDF bitmap is truncated"))?;
+
+                    for i in 0..stride {
+                        let byte = row
+                            .get(i * 2..i * 2 + 2)
+                            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                            .ok_or_else(|| anyhow!("Invalid bitmap row on line {}", number + 1))?;
+
+                        // BDF stores the leftmost pixel in the most significant bit.
+                        rows.push(byte.reverse_bits());
+                    }
+                }
+
+                let chr = match u32::try_from(encoding).ok().and_then(char::from_u32) {
+                    Some(chr) => chr,
+                    None => continue,
+                };
+
+                if !ranges.is_empty() && !ranges.iter().any(|r| r.contains(&(chr as u32))) {
+                    continue;
+                }
+
+                let glyph = BitmapGlyph {
+                    chr,
+                    metrics: GlyphMetrics {
+                        width: width.try_into()?,
+                        height: height.try_into()?,
+                        x_offset: x_offset.try_into()?,
+                        y_offset: (y_offset + height).try_into()?,
+                        advance: advance.try_into()?,
+                    },
+                    offset: 0,
+                };
+
+                glyphs.push((glyph, rows));
+            }
+            _ => {}
+        }
+    }
+
+    // Fall back to the font bounding box when the ascent or descent property is missing.
+    let (ascent, descent) = match (ascent, descent, bounding_box) {
+        (Some(ascent), Some(descent), _) => (ascent, descent),
+        (_, _, Some((height, y_offset))) => (height + y_offset, -y_offset),
+        _ => {
+            return Err(anyhow!(
+                "BDF font defines neither its ascent nor a bounding box"
+            ))
+        }
+    };
+
+    glyphs.sort_by_key(|(glyph, _)| glyph.chr);
+    glyphs.dedup_by_key(|(glyph, _)| glyph.chr);
+
+    // Packed only now, so the bitmaps of dropped duplicates are left out.
+    let mut bitmaps = Vec::new();
+    let glyphs = glyphs
+        .into_iter()
+        .map(|(glyph, rows)| {
+            let offset = bitmaps.len() as u32;
+
+            bitmaps.extend(rows);
+            BitmapGlyph { offset, ..glyph }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(BitmapFont {
+        ascent: ascent.try_into()?,
+        descent: descent.try_into()?,
+        glyphs: Cow::Owned(glyphs),
+        bitmaps: Cow::Owned(bitmaps),
+        kerning: Cow::Owned(Vec::new()),
+    })
+}
+
+/// Writes `font` as Rust source declaring a `static` named `name`, in the style of the
+/// built-in `glcdfont.rs` table.
+pub fn font_to_rust(font: &BitmapFont, name: &str, source: &str) -> String {
+    // An unused import would fail clippy wherever the generated file is checked in.
+    let imports = if font.kerning.is_empty() {
+        "BitmapFont, BitmapGlyph, GlyphMetrics"
+    } else {
+        "BitmapFont, BitmapGlyph, GlyphMetrics, KerningPair"
+    };
+    let mut out = format!(
+        "// Generated from {}, do not edit.\n\
+         use std::borrow::Cow;\n\n\
+         use crate::graphics::{{{}}};\n\n\
+         pub static {}: BitmapFont<'static> = BitmapFont {{\n    \
+         ascent: {},\n    descent: {},\n    glyphs: Cow::Borrowed(&[\n",
+        source, imports, name, font.ascent, font.descent
+    );
+
+    for glyph in font.glyphs.iter() {
+        let m = glyph.metrics;
+        out += &format!(
+            "        BitmapGlyph {{\n            chr: {:?},\n            metrics: GlyphMetrics {{\n                \
+             width: {},\n                height: {},\n                x_offset: {},\n                \
+             y_offset: {},\n                advance: {},\n            }},\n            offset: {},\n        }},\n",
+            glyph.chr, m.width, m.height, m.x_offset, m.y_offset, m.advance, glyph.offset
+        );
+    }
+
+    out += "    ]),\n    bitmaps: Cow::Borrowed(&[\n";
+
+    for chunk in font.bitmaps.chunks(16) {
+        let bytes: Vec<String> = chunk.iter().map(|b| format!("0x{:02X}", b)).collect();
+        out += &format!("        {},\n", bytes.join(", "));
+    }
+
+    out += "    ]),\n    kerning: Cow::Borrowed(&[\n";
+
+    for pair in font.kerning.iter() {
+        out += &format!(
+            "        KerningPair {{\n            left: {:?},\n            right: {:?},\n            \
+             adjust: {},\n        }},\n",
+            pair.left, pair.right, pair.adjust
+        );
+    }
+
+    out += "    ]),\n};\n";
+    out
+}
+
+fn parse_number(word: Option<&str>, line: usize) -> anyhow::Result<i32> {
+    word.ok_or_else(|| anyhow!("Missing value"))?
+        .parse()
+        .with_context(|| format!("Invalid number on line {}", line + 1))
+}
+
+fn parse_numbers<'a>(
+    words: impl Iterator<Item = &'a str>,
+    count: usize,
+    line: usize,
+) -> anyhow::Result<Vec<i32>> {
+    let values = words
+        .map(|word| parse_number(Some(word), line))
+        .collect::<anyhow::Result<Vec<i32>>>()?;
+
+    if values.len() != count {
+        return Err(anyhow!("Expected {} values on line {}", count, line + 1));
+    }
+
+    Ok(values)
+}
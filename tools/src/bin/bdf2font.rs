@@ -0,0 +1,57 @@
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{Parser, ValueEnum};
+
+use extensa_tools::bdf::{font_to_rust, parse_bdf};
+use extensa_tools::filesystem::font_to_bytes;
+use extensa_tools::parse_range;
+
+/// Converts BDF fonts into bitmap fonts for the display's text renderer.
+#[derive(Parser)]
+#[command(name = "bdf2font")]
+struct Args {
+    input: PathBuf,
+    output: PathBuf,
+    /// `font` writes a file to load from SPIFFS, `rust` writes a `static` font table.
+    #[arg(long, value_enum, default_value_t = Format::Font)]
+    format: Format,
+    /// Name of the generated `static` when writing Rust.
+    #[arg(long, default_value = "FONT")]
+    name: String,
+    /// Only keep characters in this range, e.g. `32-126` or `0x100-0x17F`. Can be repeated.
+    #[arg(long = "range", value_parser = parse_range)]
+    ranges: Vec<RangeInclusive<u32>>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Font,
+    Rust,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let text = std::fs::read_to_string(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    let font = parse_bdf(&text, &args.ranges)?;
+
+    let bytes = match args.format {
+        Format::Font => font_to_bytes(&font)?,
+        Format::Rust => {
+            font_to_rust(&font, &args.name, &args.input.display().to_string()).into_bytes()
+        }
+    };
+
+    std::fs::write(&args.output, bytes)
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+
+    println!(
+        "Wrote {} glyphs to {}",
+        font.glyphs.len(),
+        args.output.display()
+    );
+
+    Ok(())
+}
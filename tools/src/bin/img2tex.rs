@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context};
+use clap::{Parser, Subcommand};
+use image::imageops::FilterType;
+
+use extensa_tools::bitmap::{pack, unpack, Conversion};
+use extensa_tools::filesystem::Texture;
+
+/// Converts images to and from the texture format read from the SPIFFS partition.
+#[derive(Parser)]
+#[command(name = "img2tex")]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Convert a PNG, BMP or PBM image into a texture file.
+    Encode {
+        input: PathBuf,
+        output: PathBuf,
+        /// Luma level (0-255) at or above which a pixel becomes white.
+        #[arg(long, default_value_t = 128, conflicts_with = "dither")]
+        threshold: u8,
+        /// Use Floyd-Steinberg dithering instead of a hard threshold.
+        #[arg(long)]
+        dither: bool,
+        /// Resize to WIDTHxHEIGHT before converting, e.g. `400x240`.
+        #[arg(long, value_parser = parse_size)]
+        resize: Option<(u32, u32)>,
+        /// Swap black and white.
+        #[arg(long)]
+        invert: bool,
+        /// Compress every row with RLE, which pays off for large flat areas.
+        #[arg(long)]
+        compress: bool,
+    },
+    /// Render a texture file back to a PNG for inspection.
+    Decode { input: PathBuf, output: PathBuf },
+}
+
+fn parse_size(value: &str) -> anyhow::Result<(u32, u32)> {
+    let (w, h) = value
+        .split_once('x')
+        .ok_or_else(|| anyhow!("Expected WIDTHxHEIGHT, got {}", value))?;
+
+    Ok((w.parse()?, h.parse()?))
+}
+
+fn main() -> anyhow::Result<()> {
+    match Args::parse().command {
+        Command::Encode {
+            input,
+            output,
+            threshold,
+            dither,
+            resize,
+            invert,
+            compress,
+        } => {
+            let mut image = image::open(&input)
+                .with_context(|| format!("Failed to open {}", input.display()))?
+                .into_luma8();
+
+            if let Some((w, h)) = resize {
+                image = image::imageops::resize(&image, w, h, FilterType::Lanczos3);
+            }
+
+            let conversion = if dither {
+                Conversion::Dither
+            } else {
+                Conversion::Threshold(threshold)
+            };
+
+            let texture = pack(&image, conversion, invert)?;
+            let bytes = if compress {
+                texture.to_rle_bytes()
+            } else {
+                texture.to_bytes()
+            };
+
+            std::fs::write(&output, bytes)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+        }
+        Command::Decode { input, output } => {
+            let bytes = std::fs::read(&input)
+                .with_context(|| format!("Failed to read {}", input.display()))?;
+            let texture = Texture::parse(&bytes)?;
+
+            unpack(&texture)
+                .save(&output)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+        }
+    }
+
+    Ok(())
+}
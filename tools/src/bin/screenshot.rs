@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use clap::Parser;
+use image::ImageFormat;
+
+use extensa_tools::filesystem::read_screenshot_frames;
+
+/// Converts screenshots taken on the device to PNG. The input is either a PBM file saved to
+/// the SPIFFS partition or a log of the serial monitor with framed screenshots in it.
+#[derive(Parser)]
+#[command(name = "screenshot")]
+struct Args {
+    input: PathBuf,
+    /// Output PNG. When the log holds several screenshots they are numbered, `screen-1.png`,
+    /// `screen-2.png` and so on.
+    output: PathBuf,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let bytes = std::fs::read(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+
+    let screenshots = if bytes.starts_with(b"P4") {
+        vec![bytes]
+    } else {
+        read_screenshot_frames(&String::from_utf8_lossy(&bytes))?
+    };
+
+    if screenshots.is_empty() {
+        return Err(anyhow!("No screenshots in {}", args.input.display()));
+    }
+
+    for (i, pbm) in screenshots.iter().enumerate() {
+        let output = if screenshots.len() == 1 {
+            args.output.clone()
+        } else {
+            numbered(&args.output, i + 1)
+        };
+
+        image::load_from_memory_with_format(pbm, ImageFormat::Pnm)
+            .context("Invalid screenshot")?
+            .save_with_format(&output, ImageFormat::Png)
+            .with_context(|| format!("Failed to write {}", output.display()))?;
+
+        println!("{}", output.display());
+    }
+
+    Ok(())
+}
+
+fn numbered(path: &Path, number: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    path.with_file_name(format!("{}-{}.png", stem, number))
+}
@@ -0,0 +1,133 @@
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+use ab_glyph::{Font as _, FontVec, PxScale, ScaleFont as _};
+use anyhow::{anyhow, Context};
+use clap::{Parser, ValueEnum};
+
+use extensa_tools::bdf::font_to_rust;
+use extensa_tools::filesystem::font_to_bytes;
+use extensa_tools::graphics::{
+    rasterize_glyph, BitmapFont, BitmapGlyph, KerningPair, Rasterization,
+};
+use extensa_tools::parse_range;
+
+/// Rasterizes a TrueType or OpenType font at a fixed pixel size into a bitmap font, so it
+/// can be drawn without rasterizing on the device.
+#[derive(Parser)]
+#[command(name = "ttf2font")]
+struct Args {
+    input: PathBuf,
+    output: PathBuf,
+    /// Pixel size, the distance from the font's ascent to its descent.
+    #[arg(long)]
+    size: u16,
+    /// Coverage (0-255) at or above which a pixel is drawn.
+    #[arg(long, default_value_t = 128, conflicts_with = "dither")]
+    threshold: u8,
+    /// Use ordered dithering of the coverage instead of a hard threshold.
+    #[arg(long)]
+    dither: bool,
+    /// `font` writes a file to load from SPIFFS, `rust` writes a `static` font table.
+    #[arg(long, value_enum, default_value_t = Format::Font)]
+    format: Format,
+    /// Name of the generated `static` when writing Rust.
+    #[arg(long, default_value = "FONT")]
+    name: String,
+    /// Characters to include, e.g. `32-126` or `0x100-0x17F`. Can be repeated, defaults to
+    /// printable ASCII.
+    #[arg(long = "range", value_parser = parse_range)]
+    ranges: Vec<RangeInclusive<u32>>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Font,
+    Rust,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let data = std::fs::read(&args.input)
+        .with_context(|| format!("Failed to read {}", args.input.display()))?;
+    let font = FontVec::try_from_vec(data).map_err(|_| anyhow!("Invalid TrueType font"))?;
+    let scaled = font.as_scaled(PxScale::from(args.size as f32));
+    let rasterization = if args.dither {
+        Rasterization::Dither
+    } else {
+        Rasterization::Threshold(args.threshold)
+    };
+    let ranges = if args.ranges.is_empty() {
+        vec![32..=126]
+    } else {
+        args.ranges
+    };
+
+    let mut chars: Vec<char> = ranges
+        .into_iter()
+        .flatten()
+        .filter_map(char::from_u32)
+        .collect();
+    chars.sort();
+    chars.dedup();
+
+    let mut glyphs = Vec::new();
+    let mut bitmaps = Vec::new();
+
+    for chr in chars {
+        if let Some(glyph) = rasterize_glyph(&font, chr, args.size, rasterization) {
+            glyphs.push(BitmapGlyph {
+                chr,
+                metrics: glyph.metrics,
+                offset: bitmaps.len() as u32,
+            });
+            bitmaps.extend(glyph.bitmap);
+        }
+    }
+
+    let mut kerning = Vec::new();
+
+    for left in glyphs.iter() {
+        for right in glyphs.iter() {
+            let adjust = scaled
+                .kern(font.glyph_id(left.chr), font.glyph_id(right.chr))
+                .round() as i16;
+
+            if adjust != 0 {
+                kerning.push(KerningPair {
+                    left: left.chr,
+                    right: right.chr,
+                    adjust,
+                });
+            }
+        }
+    }
+
+    let bitmap_font = BitmapFont {
+        ascent: scaled.ascent().ceil() as u16,
+        descent: (-scaled.descent()).ceil() as u16,
+        glyphs: Cow::Owned(glyphs),
+        bitmaps: Cow::Owned(bitmaps),
+        kerning: Cow::Owned(kerning),
+    };
+
+    let bytes = match args.format {
+        Format::Font => font_to_bytes(&bitmap_font)?,
+        Format::Rust => {
+            font_to_rust(&bitmap_font, &args.name, &args.input.display().to_string()).into_bytes()
+        }
+    };
+
+    std::fs::write(&args.output, bytes)
+        .with_context(|| format!("Failed to write {}", args.output.display()))?;
+
+    println!(
+        "Wrote {} glyphs and {} kerning pairs to {}",
+        bitmap_font.glyphs.len(),
+        bitmap_font.kerning.len(),
+        args.output.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use image::{GrayImage, Luma};
+
+use crate::filesystem::Texture;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Conversion {
+    /// Pixels with a luma at or above the threshold become white.
+    Threshold(u8),
+    /// Floyd-Steinberg error diffusion around the middle grey.
+    Dither,
+}
+
+/// Converts a greyscale image to packed 1-bit rows in framebuffer bit order.
+pub fn pack(image: &GrayImage, conversion: Conversion, invert: bool) -> anyhow::Result<Texture> {
+    let width: u16 = image.width().try_into()?;
+    let height: u16 = image.height().try_into()?;
+    let stride = (width as usize).div_ceil(8);
+    let white = to_white_mask(image, conversion);
+    let mut data = vec![0u8; stride * height as usize];
+
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            if white[y * width as usize + x] != invert {
+                data[y * stride + x / 8] |= 1 << (x % 8);
+            }
+        }
+    }
+
+    Texture::new(width, height, data)
+}
+
+/// Renders a texture back to a black and white greyscale image.
+pub fn unpack(texture: &Texture) -> GrayImage {
+    GrayImage::from_fn(texture.width() as u32, texture.height() as u32, |x, y| {
+        let byte = texture.row(y as u16)[(x / 8) as usize];
+
+        if byte & (1 << (x % 8)) != 0 {
+            Luma([0xFF])
+        } else {
+            Luma([0x00])
+        }
+    })
+}
+
+fn to_white_mask(image: &GrayImage, conversion: Conversion) -> Vec<bool> {
+    match conversion {
+        Conversion::Threshold(level) => image.pixels().map(|p| p.0[0] >= level).collect(),
+        Conversion::Dither => dither(image),
+    }
+}
+
+fn dither(image: &GrayImage) -> Vec<bool> {
+    let width = image.width() as usize;
+    let height = image.height() as usize;
+    let mut levels: Vec<i16> = image.pixels().map(|p| p.0[0] as i16).collect();
+    let mut white = vec![false; levels.len()];
+
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            let old = levels[i];
+            let new = if old >= 128 { 255 } else { 0 };
+            let err = old - new;
+
+            white[i] = new == 255;
+
+            let mut spread = |dx: isize, dy: usize, weight: i16| {
+                let nx = x as isize + dx;
+
+                if nx >= 0 && (nx as usize) < width && y + dy < height {
+                    levels[(y + dy) * width + nx as usize] += err * weight / 16;
+                }
+            };
+
+            spread(1, 0, 7);
+            spread(-1, 1, 3);
+            spread(0, 1, 5);
+            spread(1, 1, 1);
+        }
+    }
+
+    white
+}
@@ -0,0 +1,12 @@
+#[path = "../../../src/filesystem/read_font.rs"]
+mod read_font;
+#[path = "../../../src/filesystem/rle.rs"]
+pub mod rle;
+#[path = "../../../src/filesystem/screenshot.rs"]
+mod screenshot;
+#[path = "../../../src/filesystem/texture.rs"]
+mod texture;
+
+pub use read_font::*;
+pub use screenshot::*;
+pub use texture::*;
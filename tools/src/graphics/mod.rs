@@ -0,0 +1,15 @@
+#[path = "../../../src/graphics/cp437.rs"]
+mod cp437;
+#[path = "../../../src/graphics/font.rs"]
+mod font;
+#[path = "../../../src/graphics/glcdfont.rs"]
+mod glcdfont;
+#[allow(clippy::module_inception, clippy::ptr_arg)]
+#[path = "../../../src/graphics/graphics.rs"]
+mod graphics;
+#[path = "../../../src/graphics/truetype.rs"]
+mod truetype;
+
+pub use font::*;
+pub use graphics::*;
+pub use truetype::*;
@@ -0,0 +1,30 @@
+//! Host-side helpers shared by the asset conversion tools.
+//!
+//! File formats are compiled straight from the firmware sources, laid out in the same
+//! modules as on the device, so the tools can never drift from what the device expects.
+
+// The firmware targets an older toolchain without `div_ceil`.
+#![allow(clippy::manual_div_ceil)]
+
+use std::ops::RangeInclusive;
+
+use anyhow::anyhow;
+
+pub mod bdf;
+pub mod bitmap;
+pub mod filesystem;
+pub mod graphics;
+
+/// Parses a `--range` of characters given as `START-END`, in decimal or `0x` hex.
+pub fn parse_range(value: &str) -> anyhow::Result<RangeInclusive<u32>> {
+    let parse = |number: &str| match number.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => number.parse(),
+    };
+
+    let (start, end) = value
+        .split_once('-')
+        .ok_or_else(|| anyhow!("Expected START-END, got {}", value))?;
+
+    Ok(parse(start)?..=parse(end)?)
+}